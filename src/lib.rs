@@ -37,6 +37,21 @@
 //!
 //! This should only affect value factories and [`Drop`] implementations of keys and values.
 //!
+//! Reentrant calls through a shared reference deadlock rather than observe or corrupt a half-finished operation.
+//!
+//! ## Bounded [`PineMap`](`sync::PineMap`)s panic when inserting beyond their limit
+//!
+//! An instance created through [`PineMap::with_max_entries`](`sync::PineMap::with_max_entries`)
+//! returns a [`CapacityExceeded`](`sync::CapacityExceeded`) error only from its dedicated bounded insertion methods.
+//! Any other method that would add an entry to it while it's full panics instead, without inserting.
+//! This isn't repeated on each method.
+//!
+//! ## Lookups go through [`Borrow`](`std::borrow::Borrow`), exactly like [`BTreeMap`](`std::collections::BTreeMap`)'s
+//!
+//! Keys can be looked up by any type they borrow as, but there's no conversion beyond that.
+//! For example, a map with [`Cow<str>`](`std::borrow::Cow`) keys can be searched with a `&str`,
+//! while a `&String` must be passed as `.as_str()` since `Cow<str>` doesn't implement `Borrow<String>`.
+//!
 //! ## Thread **un**safe versions of the collections don't exist yet
 //!
 //! Same as above, this will be much nicer to add once GATs land.
@@ -45,7 +60,17 @@
 #![warn(clippy::pedantic, missing_docs)]
 #![allow(clippy::semicolon_if_nothing_returned)]
 
-use std::convert::Infallible;
+use parking_lot::MappedRwLockReadGuard;
+use std::{
+	borrow::Borrow,
+	collections::{btree_map, BTreeMap},
+	convert::Infallible,
+	error::Error,
+	fmt::{self, Debug, Display},
+	ops::RangeBounds,
+	pin::Pin,
+	ptr,
+};
 
 #[cfg(all(doctest))]
 pub mod readme {
@@ -55,6 +80,399 @@ pub mod readme {
 pub mod prelude;
 pub mod sync;
 
+/// Implements [`Borrow`](`std::borrow::Borrow`) and [`BorrowMut`](`std::borrow::BorrowMut`) through unsizing coercion,
+/// so that the types can be used with [`.emplace(…)`](`prelude::UnpinnedPineMapEmplace::emplace`) on a [`PressedPineMap`](`sync::PressedPineMap`).
+///
+/// # Example
+///
+/// ```rust
+/// use pinus::{prelude::*, sync::PressedPineMap};
+/// use std::fmt::Display;
+/// use this_is_fine::prelude::*;
+///
+/// struct Concrete;
+/// impl Display for Concrete { //…
+/// #   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "Concrete") }
+/// # }
+///
+/// pinus::borrow_unsized!(Concrete => dyn Display, Concrete => dyn Display + Send);
+///
+/// let map = PressedPineMap::<_, dyn Display>::new();
+/// map.emplace(1, Concrete).ok().unwrap();
+/// ```
+#[macro_export]
+macro_rules! borrow_unsized {
+	($($type:ty => $target:ty),*$(,)?) => {$(
+		const _: () = {
+			// Keeps the trait object lifetime of `$target` where it would otherwise be elided.
+			type Target = $target;
+
+			impl ::std::borrow::Borrow<Target> for $type {
+				fn borrow(&self) -> &Target {
+					self
+				}
+			}
+
+			impl ::std::borrow::BorrowMut<Target> for $type {
+				fn borrow_mut(&mut self) -> &mut Target {
+					self
+				}
+			}
+		};
+	)*};
+}
+
+/// The error returned by [`.try_insert_with2(…)`](`prelude::UnpinnedPineMap::try_insert_with2`),
+/// with both failure modes of [`.try_insert_with(…)`](`prelude::UnpinnedPineMap::try_insert_with`) flattened into one.
+pub enum InsertError<K, F, E> {
+	/// An entry matching the key already exists. The key and factory are returned.
+	Occupied(K, F),
+	/// The value factory failed.
+	Factory(E),
+}
+
+impl<K: Debug, F, E: Debug> Debug for InsertError<K, F, E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			InsertError::Occupied(key, _) => f
+				.debug_tuple("Occupied")
+				.field(key)
+				.field(&format_args!("_"))
+				.finish(),
+			InsertError::Factory(error) => f.debug_tuple("Factory").field(error).finish(),
+		}
+	}
+}
+
+impl<K, F, E: Display> Display for InsertError<K, F, E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			InsertError::Occupied(..) => f.write_str("An entry with this key already exists."),
+			InsertError::Factory(error) => {
+				f.write_str("The value factory failed: ")?;
+				Display::fmt(error, f)
+			}
+		}
+	}
+}
+
+impl<K: Debug, F, E: Error + 'static> Error for InsertError<K, F, E> {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			InsertError::Occupied(..) => None,
+			InsertError::Factory(error) => Some(error),
+		}
+	}
+}
+
+/// Iterates mutably over a key range of a map, in key order,
+/// obtained through [`.range_mut(…)`](`prelude::UnpinnedPineMap::range_mut`).
+pub struct RangeMut<'a, K, V: ?Sized> {
+	addresses: btree_map::RangeMut<'a, K, *mut V>,
+}
+
+impl<'a, K, V: ?Sized> RangeMut<'a, K, V> {
+	/// # Safety
+	///
+	/// The values must be valid and exclusively borrowed for `'a`.
+	pub(crate) unsafe fn new(addresses: btree_map::RangeMut<'a, K, *mut V>) -> Self {
+		Self { addresses }
+	}
+}
+
+impl<'a, K, V: ?Sized> Iterator for RangeMut<'a, K, V> {
+	type Item = (&'a K, &'a mut V);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Keys are unique, so each value is visited only once.
+		let (key, value) = self.addresses.next()?;
+		Some((key, unsafe { &mut **value }))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.addresses.size_hint()
+	}
+}
+
+impl<K, V: ?Sized> DoubleEndedIterator for RangeMut<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.addresses.next_back()?;
+		Some((key, unsafe { &mut **value }))
+	}
+}
+
+/// Iterates mutably over a key range of a map with pinned values, in key order,
+/// obtained through [`.range_pinned_mut(…)`](`prelude::PinnedPineMap::range_pinned_mut`).
+pub struct RangePinnedMut<'a, K, V: ?Sized> {
+	unpinned: RangeMut<'a, K, V>,
+}
+
+impl<'a, K, V: ?Sized> RangePinnedMut<'a, K, V> {
+	/// # Safety
+	///
+	/// The values must be pinned.
+	pub(crate) unsafe fn new(unpinned: RangeMut<'a, K, V>) -> Self {
+		Self { unpinned }
+	}
+}
+
+impl<'a, K, V: ?Sized> Iterator for RangePinnedMut<'a, K, V> {
+	type Item = (&'a K, Pin<&'a mut V>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.unpinned.next()?;
+		Some((key, unsafe { Pin::new_unchecked(value) }))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.unpinned.size_hint()
+	}
+}
+
+impl<K, V: ?Sized> DoubleEndedIterator for RangePinnedMut<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.unpinned.next_back()?;
+		Some((key, unsafe { Pin::new_unchecked(value) }))
+	}
+}
+
+/// Iterates over clones of a map's keys, in key order, obtained through [`.keys()`](`prelude::UnpinnedPineMap::keys`).
+///
+/// This holds the map's read lock until dropped, so it can be shared but not sent between threads.
+pub struct Keys<'a, K, V: ?Sized> {
+	// Declared first so that it's dropped before the lock is released.
+	keys: btree_map::Keys<'a, K, *mut V>,
+	_addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>,
+}
+
+impl<'a, K, V: ?Sized> Keys<'a, K, V> {
+	pub(crate) fn new(addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>) -> Self {
+		// The map stays in place while the guard moves, and the guard outlives `keys`.
+		let keys = unsafe { &*ptr::addr_of!(*addresses) }.keys();
+		Self {
+			keys,
+			_addresses: addresses,
+		}
+	}
+}
+
+impl<K: Clone, V: ?Sized> Iterator for Keys<'_, K, V> {
+	type Item = K;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Keys can move once the lock is released, so they must not escape by reference.
+		self.keys.next().cloned()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.keys.size_hint()
+	}
+}
+
+impl<K: Clone, V: ?Sized> DoubleEndedIterator for Keys<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		self.keys.next_back().cloned()
+	}
+}
+
+impl<K: Clone, V: ?Sized> ExactSizeIterator for Keys<'_, K, V> {}
+
+/// Iterates over a map's values, in key order, obtained through [`.values()`](`prelude::UnpinnedPineMap::values`).
+///
+/// This holds the map's read lock until dropped, so it can be shared but not sent between threads.
+pub struct Values<'a, K, V: ?Sized> {
+	// Declared first so that it's dropped before the lock is released.
+	values: btree_map::Values<'a, K, *mut V>,
+	_addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>,
+}
+
+impl<'a, K, V: ?Sized> Values<'a, K, V> {
+	/// # Safety
+	///
+	/// The values must be valid for `'a`.
+	pub(crate) unsafe fn new(addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>) -> Self {
+		// The map stays in place while the guard moves, and the guard outlives `values`.
+		let values = (&*ptr::addr_of!(*addresses)).values();
+		Self {
+			values,
+			_addresses: addresses,
+		}
+	}
+}
+
+impl<'a, K, V: ?Sized> Iterator for Values<'a, K, V> {
+	type Item = &'a V;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.values.next().map(|value| unsafe { &**value })
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.values.size_hint()
+	}
+}
+
+impl<K, V: ?Sized> DoubleEndedIterator for Values<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		self.values.next_back().map(|value| unsafe { &**value })
+	}
+}
+
+impl<K, V: ?Sized> ExactSizeIterator for Values<'_, K, V> {}
+
+/// Iterates over clones of a map's keys along with references to its values, in key order,
+/// obtained through [`AppendOnly::iter`](`sync::AppendOnly::iter`).
+///
+/// This holds the map's read lock until dropped, so it can be shared but not sent between threads.
+pub struct Iter<'a, K, V: ?Sized> {
+	// Declared first so that it's dropped before the lock is released.
+	entries: btree_map::Iter<'a, K, *mut V>,
+	_addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>,
+}
+
+impl<'a, K, V: ?Sized> Iter<'a, K, V> {
+	/// # Safety
+	///
+	/// The values must be valid for `'a`.
+	pub(crate) unsafe fn new(addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>) -> Self {
+		// The map stays in place while the guard moves, and the guard outlives `entries`.
+		let entries = (&*ptr::addr_of!(*addresses)).iter();
+		Self {
+			entries,
+			_addresses: addresses,
+		}
+	}
+}
+
+impl<'a, K: Clone, V: ?Sized> Iterator for Iter<'a, K, V> {
+	type Item = (K, &'a V);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Keys can move once the lock is released, so they must not escape by reference.
+		let (key, value) = self.entries.next()?;
+		Some((key.clone(), unsafe { &**value }))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.entries.size_hint()
+	}
+}
+
+impl<K: Clone, V: ?Sized> DoubleEndedIterator for Iter<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.next_back()?;
+		Some((key.clone(), unsafe { &**value }))
+	}
+}
+
+impl<K: Clone, V: ?Sized> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// Iterates over clones of a key range's keys along with references to their values, in key order,
+/// obtained through [`.range(…)`](`prelude::UnpinnedPineMap::range`).
+///
+/// This holds the map's read lock until dropped, so it can be shared but not sent between threads.
+pub struct Range<'a, K, V: ?Sized> {
+	// Declared first so that it's dropped before the lock is released.
+	entries: btree_map::Range<'a, K, *mut V>,
+	_addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>,
+}
+
+impl<'a, K: Ord, V: ?Sized> Range<'a, K, V> {
+	/// # Safety
+	///
+	/// The values must be valid for `'a`.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`], iff `range` starts after it ends or starts and ends at the same excluded bound.
+	pub(crate) unsafe fn new<Q, R: RangeBounds<Q>>(
+		addresses: MappedRwLockReadGuard<'a, BTreeMap<K, *mut V>>,
+		range: R,
+	) -> Self
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		// The map stays in place while the guard moves, and the guard outlives `entries`.
+		let entries = (&*ptr::addr_of!(*addresses)).range(range);
+		Self {
+			entries,
+			_addresses: addresses,
+		}
+	}
+}
+
+impl<'a, K: Clone, V: ?Sized> Iterator for Range<'a, K, V> {
+	type Item = (K, &'a V);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		// Keys can move once the lock is released, so they must not escape by reference.
+		let (key, value) = self.entries.next()?;
+		Some((key.clone(), unsafe { &**value }))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.entries.size_hint()
+	}
+}
+
+impl<K: Clone, V: ?Sized> DoubleEndedIterator for Range<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.next_back()?;
+		Some((key.clone(), unsafe { &**value }))
+	}
+}
+
+/// Iterates over clones of a key range's keys along with references to their pinned values, in key order,
+/// obtained through [`.range(…)`](`prelude::PinnedPineMap::range`).
+///
+/// This holds the map's read lock until dropped, so it can be shared but not sent between threads.
+pub struct RangePinned<'a, K, V: ?Sized> {
+	unpinned: Range<'a, K, V>,
+}
+
+impl<'a, K, V: ?Sized> RangePinned<'a, K, V> {
+	/// # Safety
+	///
+	/// The values must be pinned.
+	pub(crate) unsafe fn new(unpinned: Range<'a, K, V>) -> Self {
+		Self { unpinned }
+	}
+}
+
+impl<'a, K: Clone, V: ?Sized> Iterator for RangePinned<'a, K, V> {
+	type Item = (K, Pin<&'a V>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.unpinned.next()?;
+		Some((key, unsafe { Pin::new_unchecked(value) }))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.unpinned.size_hint()
+	}
+}
+
+impl<K: Clone, V: ?Sized> DoubleEndedIterator for RangePinned<'_, K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.unpinned.next_back()?;
+		Some((key, unsafe { Pin::new_unchecked(value) }))
+	}
+}
+
+// Like `btree_map::RangeMut`, but yielding keys by shared reference.
+unsafe impl<K: Sync, V: ?Sized + Send> Send for RangeMut<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for RangeMut<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Send> Send for RangePinnedMut<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for RangePinnedMut<'_, K, V> {}
+
+// `Iter`, `Keys`, `Values`, `Range` and `RangePinned` aren't `Send`, as the lock must be released on the thread that acquired it.
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for Keys<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for Values<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for Range<'_, K, V> {}
+unsafe impl<K: Sync, V: ?Sized + Sync> Sync for RangePinned<'_, K, V> {}
+
 trait UnwrapInfallible {
 	type T;
 	fn unwrap_infallible(self) -> Self::T;
@@ -1,11 +1,15 @@
 //! The shared bulk of the API.
 #![allow(clippy::type_complexity)] // For not-quite fallible methods.
 
-use crate::UnwrapInfallible;
+use crate::{
+	InsertError, Keys, Range, RangeMut, RangePinned, RangePinnedMut, UnwrapInfallible, Values,
+};
 use std::{
 	borrow::{Borrow, BorrowMut},
 	cell::Cell,
+	collections::BTreeMap,
 	mem::{ManuallyDrop, MaybeUninit},
+	ops::{Bound, RangeBounds},
 	pin::Pin,
 };
 use tap::Pipe;
@@ -44,6 +48,279 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 		K: Borrow<Q>,
 		Q: Ord + ?Sized;
 
+	/// Returns a copy of the value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_copied<Q>(&self, key: &Q) -> Option<V>
+	where
+		V: Copy,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.get(key).copied()
+	}
+
+	/// Returns a reference to the value corresponding to the key, or panics with a message derived from it.
+	///
+	/// `message` is only called if there is no such entry.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Iff no entry matching `key` exists.
+	#[track_caller]
+	fn get_expect<Q, F: FnOnce(&Q) -> String>(&self, key: &Q, message: F) -> &V
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		match self.get(key) {
+			Some(value) => value,
+			None => panic!("{}", message(key)),
+		}
+	}
+
+	/// Returns a reference to the value corresponding to exactly the key type.
+	///
+	/// This is [`.get(…)`](`UnpinnedPineMap::get`) without the borrowed-form indirection,
+	/// for call sites where type inference or profiling benefits from spelling that out.
+	fn get_exact(&self, key: &K) -> Option<&V> {
+		self.get(key)
+	}
+
+	/// Returns the number of entries.
+	fn len(&self) -> usize;
+
+	/// Checks whether there are no entries.
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Checks whether an entry matching `key` exists.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn contains_key<Q>(&self, key: &Q) -> bool
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
+	/// Returns the number of entries with keys in `range`.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// This takes linear time in the number of counted entries.
+	fn count_range<Q, R: RangeBounds<Q>>(&self, range: R) -> usize
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
+	/// Collects the entries with keys in `range` into a [`BTreeMap`], under a single lock acquisition.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	/// The value references remain valid, as values can only be removed through an exclusive reference.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`], iff `range` starts after it ends or starts and ends at the same excluded bound.
+	fn range_as_map<Q, R: RangeBounds<Q>>(&self, range: R) -> BTreeMap<K, &V>
+	where
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized;
+
+	/// Iterates mutably over the entries with keys in `range`, in key order.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range_mut`](`std::collections::BTreeMap::range_mut`),
+	/// iff `range` starts after it ends or starts and ends at the same excluded bound.
+	fn range_mut<Q, R: RangeBounds<Q>>(&mut self, range: R) -> RangeMut<'_, K, V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
+	/// Iterates over clones of the keys in `range` along with references to their values, in key order.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread in the meantime deadlocks.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`](`std::collections::BTreeMap::range`),
+	/// iff `range` starts after it ends or starts and ends at the same excluded bound.
+	fn range<Q, R: RangeBounds<Q>>(&self, range: R) -> Range<'_, K, V>
+	where
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized;
+
+	/// Iterates over clones of the keys between `start` and `end` along with references to their values, in key order.
+	///
+	/// This is [`.range(…)`](`UnpinnedPineMap::range`) with a [`Bound`] pair,
+	/// which is convenient where bounds are built dynamically.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`](`std::collections::BTreeMap::range`),
+	/// iff `start` is after `end` or both are the same excluded bound.
+	fn range_bounds<Q>(&self, start: Bound<&Q>, end: Bound<&Q>) -> Range<'_, K, V>
+	where
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		self.range((start, end))
+	}
+
+	/// Iterates mutably over the entries with keys between `start` and `end`, in key order.
+	///
+	/// This is [`.range_mut(…)`](`UnpinnedPineMap::range_mut`) with a [`Bound`] pair,
+	/// which is convenient where bounds are built dynamically.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range_mut`](`std::collections::BTreeMap::range_mut`),
+	/// iff `start` is after `end` or both are the same excluded bound.
+	fn range_bounds_mut<Q>(&mut self, start: Bound<&Q>, end: Bound<&Q>) -> RangeMut<'_, K, V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.range_mut((start, end))
+	}
+
+	/// Checks whether entries matching all of `keys` exist, stopping at the first missing one.
+	///
+	/// All keys are checked under a single lock acquisition, so the result is consistent.
+	/// `keys` must not access this map while being iterated.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn contains_all<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: Borrow<Q>,
+		Q: 'a + Ord + ?Sized;
+
+	/// Checks whether an entry matching any of `keys` exists, stopping at the first present one.
+	///
+	/// All keys are checked under a single lock acquisition, so the result is consistent.
+	/// `keys` must not access this map while being iterated.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn contains_any<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: Borrow<Q>,
+		Q: 'a + Ord + ?Sized;
+
+	/// Returns the address of the value corresponding to the key.
+	///
+	/// Values aren't moved by the collection until they are removed, so this is stable across other insertions and removals.
+	/// The only exceptions are exclusive methods that explicitly relocate values,
+	/// namely [`PineMap::shrink_to`](`crate::sync::PineMap::shrink_to`) (and so [`PineMap::retain_and_compact`](`crate::sync::PineMap::retain_and_compact`))
+	/// and [`PressedPineMap::swap_remove`](`crate::sync::PressedPineMap::swap_remove`), none of which are available on pinned instances.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn stable_address<Q>(&self, key: &Q) -> Option<usize>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.get(key)
+			.map(|value| (value as *const V).cast::<u8>() as usize)
+	}
+
+	/// Returns a clone of the key and a reference to the value at position `index` in key order.
+	///
+	/// The key is cloned because keys may move as soon as the instance is unlocked again.
+	///
+	/// This takes linear time.
+	fn get_by_index(&self, index: usize) -> Option<(K, &V)>
+	where
+		K: Clone;
+
+	/// Returns the position of the matching entry in key order.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// This takes linear time.
+	fn index_of<Q>(&self, key: &Q) -> Option<usize>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
+	/// Returns a clone of the stored key that matches `key`, without accessing the value.
+	///
+	/// The key is cloned because keys may move as soon as the instance is unlocked again.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_key<Q>(&self, key: &Q) -> Option<K>
+	where
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized;
+
+	/// Folds all values in key order, under a single lock acquisition.
+	///
+	/// The instance stays locked while `f` runs, so `f` must not access it.
+	fn fold_values<B, F: FnMut(B, &V) -> B>(&self, init: B, f: F) -> B;
+
+	/// Collects references to all values in key order, under a single lock acquisition.
+	///
+	/// The references remain valid after the lock is released, as values can only be removed through an exclusive reference.
+	fn values_vec(&self) -> Vec<&V>;
+
+	/// Copies all entries into a [`Vec`] in key order, under a single lock acquisition.
+	///
+	/// Unlike [`PineMap::into_sorted_vec`](`crate::sync::PineMap::into_sorted_vec`), this doesn't consume the instance.
+	fn to_vec(&self) -> Vec<(K, V)>
+	where
+		K: Copy,
+		V: Copy;
+
+	/// Iterates over clones of all keys, in key order.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread in the meantime deadlocks.
+	fn keys(&self) -> Keys<'_, K, V>
+	where
+		K: Clone;
+
+	/// Iterates over all values, in key order.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread in the meantime deadlocks.
+	fn values(&self) -> Values<'_, K, V>;
+
+	/// Returns a reference to the value corresponding to the smallest key.
+	fn first_value(&self) -> Option<&V>;
+
+	/// Returns a reference to the value corresponding to the largest key.
+	fn last_value(&self) -> Option<&V>;
+
 	/// Tries to insert a new value produced by the given factory, but only if no such key exists yet.
 	///
 	/// # Errors
@@ -59,6 +336,30 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 	where
 		V: Sized;
 
+	/// Tries to insert a new value produced by the given factory, but only if no such key exists yet.
+	///
+	/// This is [`.try_insert_with(…)`](`UnpinnedPineMap::try_insert_with`) with a single flat error type.
+	///
+	/// # Errors
+	///
+	/// [`InsertError::Occupied`] iff an entry matching `key` already exists.
+	///
+	/// [`InsertError::Factory`] iff `value_factory` fails.
+	fn try_insert_with2<F: FnOnce(&K) -> Result<V, E>, E>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Result<&V, InsertError<K, F, E>>
+	where
+		V: Sized,
+	{
+		match self.try_insert_with(key, value_factory) {
+			Ok((value, Ok(()))) => Ok(value),
+			Ok((_, Err((key, value_factory)))) => Err(InsertError::Occupied(key, value_factory)),
+			Err(error) => Err(InsertError::Factory(error)),
+		}
+	}
+
 	/// Inserts a new value produced by the given factory, but only if no such key exists yet.
 	///
 	/// # Errors
@@ -90,6 +391,69 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 			.map_err(|(key, _)| (key, value.take().expect("unreachable")))
 	}
 
+	/// Inserts a new value, but only if no such key exists yet,
+	/// and otherwise calls `on_conflict` with the existing value.
+	///
+	/// `on_conflict` runs under the same write lock as the failed insertion,
+	/// so no other thread can insert in the meantime, but it also must not access this instance.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	fn insert_or_inspect<F: FnOnce(&V)>(
+		&self,
+		key: K,
+		value: V,
+		on_conflict: F,
+	) -> Fine<&V, (K, V)>
+	where
+		V: Sized;
+
+	/// Always builds a value with the given factory and inserts it, but only if no such key exists yet.
+	///
+	/// Unlike with [`.insert_with(…)`](`UnpinnedPineMap::insert_with`), the factory runs before the instance is locked.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists, with the value that was built but not inserted.
+	/// `key` is dropped in that case.
+	fn build_or_get<F: FnOnce(&K) -> V>(&self, key: K, value_factory: F) -> Result<&V, V>
+	where
+		V: Sized,
+	{
+		let value = value_factory(&key);
+		match self.insert(key, value) {
+			(value, Ok(())) => Ok(value),
+			(_, Err((_, value))) => Err(value),
+		}
+	}
+
+	/// Returns a reference to the value corresponding to the key,
+	/// inserting a new value produced by the given factory first if no such key exists yet.
+	///
+	/// # Returns
+	///
+	/// The value reference and whether a new entry was inserted.
+	fn get_or_insert_with_status<F: FnOnce(&K) -> V>(&self, key: K, value_factory: F) -> (&V, bool)
+	where
+		V: Sized,
+	{
+		let (value, inserted) = self.insert_with(key, value_factory);
+		(value, inserted.is_ok())
+	}
+
+	/// Returns a reference to the value corresponding to the key,
+	/// inserting [`V::default()`](`Default::default`) first if no such key exists yet.
+	///
+	/// The check and insertion happen under a single write lock acquisition,
+	/// so concurrent calls with equal keys create only one default value between them.
+	fn get_default(&self, key: K) -> &V
+	where
+		V: Sized + Default,
+	{
+		self.insert_with(key, |_| V::default()).0
+	}
+
 	/// Clears the map, removing all elements.
 	///
 	/// # Panics
@@ -106,6 +470,76 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 		K: Borrow<Q>,
 		Q: Ord + ?Sized;
 
+	/// Returns a mutable reference to the value corresponding to exactly the key type.
+	///
+	/// This is [`.get_mut(…)`](`UnpinnedPineMap::get_mut`) without the borrowed-form indirection.
+	fn get_exact_mut(&mut self, key: &K) -> Option<&mut V> {
+		self.get_mut(key)
+	}
+
+	/// Runs `f` on the value corresponding to the key, if present, and returns its result.
+	///
+	/// The borrow of the value ends when `f` returns, so sequential mutations don't conflict.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn modify<Q, R, F: FnOnce(&mut V) -> R>(&mut self, key: &Q, f: F) -> Option<R>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.get_mut(key).map(f)
+	}
+
+	/// Replaces the value corresponding to the key with `new`, but only if it's currently equal to `expected`.
+	///
+	/// The old value is dropped in place and `new` is written into the same slot.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Errors
+	///
+	/// Iff the current value doesn't equal `expected`, with a reference to the current value,
+	/// or there is no matching entry, with [`None`].
+	fn compare_and_swap<Q>(&mut self, key: &Q, expected: &V, new: V) -> Result<&V, Option<&V>>
+	where
+		V: Sized + PartialEq,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		match self.get_mut(key) {
+			Some(current) if current == expected => {
+				*current = new;
+				Ok(current)
+			}
+			current => Err(current.map(|current| &*current)),
+		}
+	}
+
+	/// Returns a reference to the stored key and a mutable reference to the value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_key_value_mut<Q>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
+	/// Returns mutable references to the values corresponding to several distinct keys at once.
+	///
+	/// The keys may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Iff any two of `keys` are equal or, given an inconsistent [`Ord`] implementation,
+	/// find the same (not zero-sized) value.
+	fn get_disjoint_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> [Option<&mut V>; N]
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
 	/// Tries to insert a new value produced by the given factory, but only if no such key exists yet.
 	///
 	/// # Errors
@@ -134,26 +568,57 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 	where
 		V: Sized, // Just for clarity.
 	{
-		let value_factory = Cell::new(Some(value_factory));
-		self.try_insert_with_mut(key, |key| {
-			value_factory.take().expect("unreachable")(key).pipe(Ok)
-		})
-		.unwrap_infallible()
-		.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
+		let value_factory = Cell::new(Some(value_factory));
+		self.try_insert_with_mut(key, |key| {
+			value_factory.take().expect("unreachable")(key).pipe(Ok)
+		})
+		.unwrap_infallible()
+		.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
+	}
+
+	/// Inserts a new value, but only if no such key exists yet.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	fn insert_mut(&mut self, key: K, value: V) -> Fine<&mut V, (K, V)>
+	where
+		V: Sized,
+	{
+		let value = Cell::new(Some(value));
+		self.insert_with_mut(key, |_| value.take().expect("unreachable"))
+			.map_err(|(key, _)| (key, value.take().expect("unreachable")))
+	}
+
+	/// Returns a mutable reference to the value corresponding to the key, through a pinned reference to the instance.
+	///
+	/// This is [`.get_mut(…)`](`UnpinnedPineMap::get_mut`) for instances embedded in pinned structs.
+	/// (The collections in this crate are [`Unpin`], so this doesn't pin their values.)
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_pin_mut<Q>(self: Pin<&mut Self>, key: &Q) -> Option<&mut V>
+	where
+		Self: Unpin,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		Pin::into_inner(self).get_mut(key)
 	}
 
-	/// Inserts a new value, but only if no such key exists yet.
+	/// Inserts a new value, but only if no such key exists yet, through a pinned reference to the instance.
+	///
+	/// This is [`.insert_mut(…)`](`UnpinnedPineMap::insert_mut`) for instances embedded in pinned structs.
 	///
 	/// # Errors
 	///
 	/// Iff an entry matching `key` already exists.
-	fn insert_mut(&mut self, key: K, value: V) -> Fine<&mut V, (K, V)>
+	fn insert_pin_mut(self: Pin<&mut Self>, key: K, value: V) -> Fine<&mut V, (K, V)>
 	where
+		Self: Unpin,
 		V: Sized,
 	{
-		let value = Cell::new(Some(value));
-		self.insert_with_mut(key, |_| value.take().expect("unreachable"))
-			.map_err(|(key, _)| (key, value.take().expect("unreachable")))
+		Pin::into_inner(self).insert_mut(key, value)
 	}
 
 	/// Removes and returns a key-value pair if a matching key exists.
@@ -173,6 +638,25 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 		self.remove_pair(key).map(|(_, v)| v)
 	}
 
+	/// Removes the value corresponding to the key into `out`, replacing (and dropping) any value it held,
+	/// and returns whether a matching key existed.
+	///
+	/// If no matching key exists, `out` is left unchanged.
+	fn remove_value_into<Q>(&mut self, key: &Q, out: &mut Option<V>) -> bool
+	where
+		V: Sized,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		match self.remove_value(key) {
+			Some(value) => {
+				*out = Some(value);
+				true
+			}
+			None => false,
+		}
+	}
+
 	/// Removes and returns a key if a matching key exists.
 	///
 	/// The value is dropped, and the collection isn't poisoned if this causes a panic.
@@ -195,6 +679,64 @@ pub trait UnpinnedPineMap<K: Ord, V: ?Sized> {
 	{
 		self.remove_key(key).is_some()
 	}
+
+	/// Keeps only the entries for which `f` returns `true`, which may also modify the values it keeps.
+	///
+	/// Removed values are dropped in place, even if some of them panic while being done so.
+	///
+	/// # Returns
+	///
+	/// How many entries were removed.
+	fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F) -> usize;
+
+	/// Keeps only the first `len` entries in key order.
+	///
+	/// Removed values are dropped in place, even if some of them panic while being done so.
+	fn truncate(&mut self, len: usize);
+
+	/// Moves the value corresponding to `old` to the key `new`, without moving the value itself.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Returns
+	///
+	/// The previous key, or [`None`] if there is no entry matching `old` (in which case `new` is dropped).
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `new` already exists, in which case nothing changes.
+	fn replace_key<Q>(&mut self, old: &Q, new: K) -> Result<Option<K>, K>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized;
+
+	/// Calls `f` on each entry in key order, stopping at the first error.
+	///
+	/// Changes made to values before that error are kept.
+	///
+	/// # Errors
+	///
+	/// Iff `f` fails, with that error.
+	fn try_for_each_mut<F: FnMut(&K, &mut V) -> Result<(), E>, E>(&mut self, f: F)
+		-> Result<(), E>;
+
+	/// Removes a matching key and moves it into `reuse`, so that its resources can be reused for another key.
+	///
+	/// The value is dropped, and the collection isn't poisoned if this causes a panic.
+	///
+	/// `reuse` is left unchanged if there is no matching entry.
+	///
+	/// # Returns
+	///
+	/// Whether a matching entry was found.
+	fn remove_key_into<Q>(&mut self, key: &Q, reuse: &mut Option<K>) -> bool
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.remove_key(key).map(|key| *reuse = Some(key)).is_some()
+	}
 }
 
 /// The unpinned emplacement API.
@@ -212,6 +754,41 @@ pub trait UnpinnedPineMapEmplace<K: Ord, V: ?Sized, W>: UnpinnedPineMap<K, V> {
 		value_factory: F,
 	) -> Result<Fine<&V, (K, F)>, E>;
 
+	/// Tries to emplace a new value produced by the given factory, but only if no such key exists yet
+	/// and the factory doesn't decline by returning [`None`].
+	///
+	/// If the factory declines, `key` is dropped and the slot is left for reuse (where the implementation supports that),
+	/// with [`None`] returned in place of a value reference.
+	///
+	/// # Errors
+	///
+	/// Outer error: Iff `value_factory` fails.
+	///
+	/// Inner error: Iff an entry matching `key` already exists.
+	fn try_emplace_opt<
+		F: for<'a> FnOnce(&K, &'a mut MaybeUninit<W>) -> Option<Result<&'a mut V, E>>,
+		E,
+	>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Result<Fine<Option<&V>, (K, F)>, E> {
+		let value_factory = Cell::new(Some(value_factory));
+		match self.try_emplace_with(key, |key, slot| {
+			value_factory.take().expect("unreachable")(key, slot)
+				.ok_or(None)?
+				.map_err(Some)
+		}) {
+			Ok((value, Ok(()))) => Ok((Some(value), Ok(()))),
+			Ok((value, Err((key, _)))) => Ok((
+				Some(value),
+				Err((key, value_factory.take().expect("unreachable"))),
+			)),
+			Err(None) => Ok((None, Ok(()))),
+			Err(Some(error)) => Err(error),
+		}
+	}
+
 	/// Emplaces a new value produced by the given factory, but only if no such key exists yet.
 	///
 	/// # Errors
@@ -230,6 +807,22 @@ pub trait UnpinnedPineMapEmplace<K: Ord, V: ?Sized, W>: UnpinnedPineMap<K, V> {
 		.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
 	}
 
+	/// Emplaces a new value produced by the given factory, but only if no such key exists yet,
+	/// and passes the new entry's position in key order to the factory.
+	///
+	/// The position is the number of existing entries with smaller keys,
+	/// which is the index the new entry has right after insertion.
+	/// The factory runs under the same lock acquisition as the insertion, so that index is exact.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	fn emplace_with_index<F: for<'a> FnOnce(&K, usize, &'a mut MaybeUninit<W>) -> &'a mut V>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Fine<&V, (K, F)>;
+
 	/// Emplaces a new value, but only if no such key exists yet.
 	///
 	/// # Errors
@@ -295,6 +888,40 @@ pub trait UnpinnedPineMapEmplace<K: Ord, V: ?Sized, W>: UnpinnedPineMap<K, V> {
 		})
 		.map_err(|(key, _)| (key, value.take().expect("unreachable")))
 	}
+
+	/// Emplaces a new value produced by the given factory, but only if no such key exists yet,
+	/// through a pinned reference to the instance.
+	///
+	/// This is [`.emplace_with_mut(…)`](`UnpinnedPineMapEmplace::emplace_with_mut`) for instances embedded in pinned structs.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	fn emplace_with_pin_mut<F: for<'a> FnOnce(&K, &'a mut MaybeUninit<W>) -> &'a mut V>(
+		self: Pin<&mut Self>,
+		key: K,
+		value_factory: F,
+	) -> Fine<&mut V, (K, F)>
+	where
+		Self: Unpin,
+	{
+		Pin::into_inner(self).emplace_with_mut(key, value_factory)
+	}
+
+	/// Emplaces a new value, but only if no such key exists yet, through a pinned reference to the instance.
+	///
+	/// This is [`.emplace_mut(…)`](`UnpinnedPineMapEmplace::emplace_mut`) for instances embedded in pinned structs.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	fn emplace_pin_mut(self: Pin<&mut Self>, key: K, value: W) -> Fine<&mut V, (K, W)>
+	where
+		Self: Unpin,
+		W: BorrowMut<V>,
+	{
+		Pin::into_inner(self).emplace_mut(key, value)
+	}
 }
 
 /// The pinned API, which disallows moving values in safe Rust.
@@ -323,63 +950,292 @@ pub unsafe trait PinnedPineMap<K: Ord, V: ?Sized> {
 		Self: Sized,
 		V: Unpin,
 	{
-		unsafe { self.unpin_unchecked() }
+		unsafe { self.unpin_unchecked() }
+	}
+
+	/// Unpins this collection.
+	///
+	/// # Safety
+	///
+	/// Pinning invariants for any remaining values `V` must still be upheld.
+	///
+	/// If [`V: Unpin`](`Unpin`), use [`.unpin()`](`PinnedPineMap::unpin`) instead.
+	unsafe fn unpin_unchecked(self) -> Self::Unpinned
+	where
+		Self: Sized,
+	{
+		(ManuallyDrop::new(self).borrow_mut() as *mut ManuallyDrop<Self>)
+			.cast::<Self::Unpinned>()
+			.read()
+	}
+
+	/// Access the unpinned API.
+	fn as_unpinned(&self) -> &Self::Unpinned {
+		unsafe { &*(self as *const Self).cast() }
+	}
+
+	/// Access the unpinned mutable API.
+	///
+	/// This is safe as `V` is `Unpin`,
+	/// so the values are guaranteed to be movable.
+	fn as_unpinned_mut(&mut self) -> &mut Self::Unpinned
+	where
+		V: Unpin,
+	{
+		unsafe { self.as_unpinned_mut_unchecked() }
+	}
+
+	/// Access the unpinned mutable API without requiring `V: Unpin`.
+	///
+	/// > If `V: Unpin`, use [`.as_unpinned_mut()`](`PinnedPineMap::as_unpinned_mut`) instead!
+	///
+	/// # Safety
+	///
+	/// Pinning invariants for any remaining values `V` must still be upheld.
+	unsafe fn as_unpinned_mut_unchecked(&mut self) -> &mut Self::Unpinned {
+		&mut *(self as *mut Self).cast()
+	}
+
+	/// Returns a reference to the value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get<Q>(&self, key: &Q) -> Option<Pin<&V>>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned()
+			.get(key)
+			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
+	}
+
+	/// Returns a copy of the pinned value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_copied<Q>(&self, key: &Q) -> Option<V>
+	where
+		V: Copy,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned().get_copied(key)
+	}
+
+	/// Returns a reference to the pinned value corresponding to the key, or panics with a message derived from it.
+	///
+	/// `message` is only called if there is no such entry.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Iff no entry matching `key` exists.
+	#[track_caller]
+	fn get_expect<Q, F: FnOnce(&Q) -> String>(&self, key: &Q, message: F) -> Pin<&V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let value = self.as_unpinned().get_expect(key, message);
+		unsafe { Pin::new_unchecked(&*(value as *const _)) }
+	}
+
+	/// Returns a reference to the pinned value corresponding to exactly the key type.
+	///
+	/// This is [`.get(…)`](`PinnedPineMap::get`) without the borrowed-form indirection.
+	fn get_exact(&self, key: &K) -> Option<Pin<&V>> {
+		self.get(key)
+	}
+
+	/// Returns the number of entries.
+	fn len(&self) -> usize {
+		self.as_unpinned().len()
+	}
+
+	/// Checks whether there are no entries.
+	fn is_empty(&self) -> bool {
+		self.as_unpinned().is_empty()
+	}
+
+	/// Checks whether an entry matching `key` exists.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn contains_key<Q>(&self, key: &Q) -> bool
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned().contains_key(key)
+	}
+
+	/// Returns the number of entries with keys in `range`.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// This takes linear time in the number of counted entries.
+	fn count_range<Q, R: RangeBounds<Q>>(&self, range: R) -> usize
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned().count_range(range)
+	}
+
+	/// Checks whether entries matching all of `keys` exist, stopping at the first missing one.
+	///
+	/// All keys are checked under a single lock acquisition, so the result is consistent.
+	/// `keys` must not access this map while being iterated.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn contains_all<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: Borrow<Q>,
+		Q: 'a + Ord + ?Sized,
+	{
+		self.as_unpinned().contains_all(keys)
+	}
+
+	/// Checks whether an entry matching any of `keys` exists, stopping at the first present one.
+	///
+	/// All keys are checked under a single lock acquisition, so the result is consistent.
+	/// `keys` must not access this map while being iterated.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn contains_any<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: Borrow<Q>,
+		Q: 'a + Ord + ?Sized,
+	{
+		self.as_unpinned().contains_any(keys)
+	}
+
+	/// Returns the address of the value corresponding to the key.
+	///
+	/// As the values are pinned, this won't change until the value is dropped.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn stable_address<Q>(&self, key: &Q) -> Option<usize>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned().stable_address(key)
+	}
+
+	/// Returns a clone of the key and a reference to the pinned value at position `index` in key order.
+	///
+	/// The key is cloned because keys may move as soon as the instance is unlocked again.
+	///
+	/// This takes linear time.
+	fn get_by_index(&self, index: usize) -> Option<(K, Pin<&V>)>
+	where
+		K: Clone,
+	{
+		self.as_unpinned()
+			.get_by_index(index)
+			.map(|(key, value)| (key, unsafe { Pin::new_unchecked(&*(value as *const _)) }))
+	}
+
+	/// Returns the position of the matching entry in key order.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// This takes linear time.
+	fn index_of<Q>(&self, key: &Q) -> Option<usize>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned().index_of(key)
 	}
 
-	/// Unpins this collection.
+	/// Returns a clone of the stored key that matches `key`, without accessing the value.
 	///
-	/// # Safety
-	///
-	/// Pinning invariants for any remaining values `V` must still be upheld.
+	/// The key is cloned because keys may move as soon as the instance is unlocked again.
 	///
-	/// If [`V: Unpin`](`Unpin`), use [`.unpin()`](`PinnedPineMap::unpin`) instead.
-	unsafe fn unpin_unchecked(self) -> Self::Unpinned
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_key<Q>(&self, key: &Q) -> Option<K>
 	where
-		Self: Sized,
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
 	{
-		(ManuallyDrop::new(self).borrow_mut() as *mut ManuallyDrop<Self>)
-			.cast::<Self::Unpinned>()
-			.read()
+		self.as_unpinned().get_key(key)
 	}
 
-	/// Access the unpinned API.
-	fn as_unpinned(&self) -> &Self::Unpinned {
-		unsafe { &*(self as *const Self).cast() }
+	/// Folds all pinned values in key order, under a single lock acquisition.
+	///
+	/// The instance stays locked while `f` runs, so `f` must not access it.
+	fn fold_values<B, F: FnMut(B, Pin<&V>) -> B>(&self, init: B, mut f: F) -> B {
+		self.as_unpinned().fold_values(init, |acc, value| {
+			f(acc, unsafe { Pin::new_unchecked(value) })
+		})
 	}
 
-	/// Access the unpinned mutable API.
+	/// Collects the entries with keys in `range` into a [`BTreeMap`] with pinned values, under a single lock acquisition.
 	///
-	/// This is safe as `V` is `Unpin`,
-	/// so the values are guaranteed to be movable.
-	fn as_unpinned_mut(&mut self) -> &mut Self::Unpinned
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`], iff `range` starts after it ends or starts and ends at the same excluded bound.
+	fn range_as_map<Q, R: RangeBounds<Q>>(&self, range: R) -> BTreeMap<K, Pin<&V>>
 	where
-		V: Unpin,
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
 	{
-		unsafe { self.as_unpinned_mut_unchecked() }
+		self.as_unpinned()
+			.range_as_map(range)
+			.into_iter()
+			.map(|(key, value)| (key, unsafe { Pin::new_unchecked(&*(value as *const _)) }))
+			.collect()
 	}
 
-	/// Access the unpinned mutable API without requiring `V: Unpin`.
-	///
-	/// > If `V: Unpin`, use [`.as_unpinned_mut()`](`PinnedPineMap::as_unpinned_mut`) instead!
-	///
-	/// # Safety
+	/// Collects references to all pinned values in key order, under a single lock acquisition.
 	///
-	/// Pinning invariants for any remaining values `V` must still be upheld.
-	unsafe fn as_unpinned_mut_unchecked(&mut self) -> &mut Self::Unpinned {
-		&mut *(self as *mut Self).cast()
+	/// The references remain valid after the lock is released, as values can only be removed through an exclusive reference.
+	fn values_vec(&self) -> Vec<Pin<&V>> {
+		self.as_unpinned()
+			.values_vec()
+			.into_iter()
+			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
+			.collect()
 	}
 
-	/// Returns a reference to the value corresponding to the key.
+	/// Copies all entries into a [`Vec`] in key order, under a single lock acquisition.
 	///
-	/// The key may be any borrowed form of the map's key type,
-	/// but the ordering on the borrowed form *must* match the ordering on the key type.
-	fn get<Q>(&self, key: &Q) -> Option<Pin<&V>>
+	/// Copying a value out doesn't move the pinned original.
+	fn to_vec(&self) -> Vec<(K, V)>
 	where
-		K: Borrow<Q>,
-		Q: Ord + ?Sized,
+		K: Copy,
+		V: Copy,
 	{
+		self.as_unpinned().to_vec()
+	}
+
+	/// Returns a reference to the pinned value corresponding to the smallest key.
+	fn first_value(&self) -> Option<Pin<&V>> {
 		self.as_unpinned()
-			.get(key)
+			.first_value()
+			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
+	}
+
+	/// Returns a reference to the pinned value corresponding to the largest key.
+	fn last_value(&self) -> Option<Pin<&V>> {
+		self.as_unpinned()
+			.last_value()
 			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
 	}
 
@@ -432,6 +1288,83 @@ pub unsafe trait PinnedPineMap<K: Ord, V: ?Sized> {
 			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
 	}
 
+	/// Inserts a new value, but only if no such key exists yet,
+	/// and otherwise calls `on_conflict` with the existing pinned value.
+	///
+	/// `on_conflict` runs under the same write lock as the failed insertion,
+	/// so no other thread can insert in the meantime, but it also must not access this instance.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	fn insert_or_inspect<F: FnOnce(Pin<&V>)>(
+		&self,
+		key: K,
+		value: V,
+		on_conflict: F,
+	) -> Fine<Pin<&V>, (K, V)>
+	where
+		V: Sized,
+	{
+		self.as_unpinned()
+			.insert_or_inspect(key, value, |value| {
+				on_conflict(unsafe { Pin::new_unchecked(value) })
+			})
+			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
+	}
+
+	/// Always builds a value with the given factory and inserts it, but only if no such key exists yet.
+	///
+	/// Unlike with [`.insert_with(…)`](`PinnedPineMap::insert_with`), the factory runs before the instance is locked.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists, with the value that was built but not inserted.
+	/// `key` is dropped in that case.
+	fn build_or_get<F: FnOnce(&K) -> V>(&self, key: K, value_factory: F) -> Result<Pin<&V>, V>
+	where
+		V: Sized,
+	{
+		self.as_unpinned()
+			.build_or_get(key, value_factory)
+			.map(|value| unsafe { Pin::new_unchecked(&*(value as *const _)) })
+	}
+
+	/// Returns a reference to the pinned value corresponding to the key,
+	/// inserting a new value produced by the given factory first if no such key exists yet.
+	///
+	/// # Returns
+	///
+	/// The value reference and whether a new entry was inserted.
+	fn get_or_insert_with_status<F: FnOnce(&K) -> V>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> (Pin<&V>, bool)
+	where
+		V: Sized,
+	{
+		let (value, inserted) = self
+			.as_unpinned()
+			.get_or_insert_with_status(key, value_factory);
+		(
+			unsafe { Pin::new_unchecked(&*(value as *const _)) },
+			inserted,
+		)
+	}
+
+	/// Returns a reference to the pinned value corresponding to the key,
+	/// inserting [`V::default()`](`Default::default`) first if no such key exists yet.
+	///
+	/// The check and insertion happen under a single write lock acquisition,
+	/// so concurrent calls with equal keys create only one default value between them.
+	fn get_default(&self, key: K) -> Pin<&V>
+	where
+		V: Sized + Default,
+	{
+		unsafe { Pin::new_unchecked(&*(self.as_unpinned().get_default(key) as *const _)) }
+	}
+
 	/// Clears the map, removing all elements.
 	///
 	/// # Panics
@@ -443,6 +1376,10 @@ pub unsafe trait PinnedPineMap<K: Ord, V: ?Sized> {
 
 	/// Returns a mutable reference to the pinned value corresponding to the key.
 	///
+	/// To call several methods that take `self: Pin<&mut Self>` on the result,
+	/// reborrow it with [`Pin::as_mut`] for each call except the last,
+	/// or use [`.modify_pin(…)`](`PinnedPineMap::modify_pin`) to do so within a closure.
+	///
 	/// The key may be any borrowed form of the map's key type,
 	/// but the ordering on the borrowed form *must* match the ordering on the key type.
 	fn get_mut<'a, Q>(&'a mut self, key: &Q) -> Option<Pin<&'a mut V>>
@@ -458,6 +1395,163 @@ pub unsafe trait PinnedPineMap<K: Ord, V: ?Sized> {
 		}
 	}
 
+	/// Runs `f` on the pinned value corresponding to the key, if present, and returns its result.
+	///
+	/// The borrow of the value ends when `f` returns, so sequential mutations don't conflict.
+	/// Within `f`, reborrow the value with [`Pin::as_mut`] to pass it to several methods that consume a `Pin<&mut V>`.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn modify_pin<Q, R, F: FnOnce(Pin<&mut V>) -> R>(&mut self, key: &Q, f: F) -> Option<R>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.get_mut(key).map(f)
+	}
+
+	/// Returns a mutable reference to the pinned value corresponding to exactly the key type.
+	///
+	/// This is [`.get_mut(…)`](`PinnedPineMap::get_mut`) without the borrowed-form indirection.
+	fn get_exact_mut<'a>(&'a mut self, key: &K) -> Option<Pin<&'a mut V>>
+	where
+		Self::Unpinned: 'a,
+	{
+		self.get_mut(key)
+	}
+
+	/// Returns a reference to the stored key and a mutable reference to the pinned value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	fn get_key_value_mut<'a, Q>(&'a mut self, key: &Q) -> Option<(&'a K, Pin<&'a mut V>)>
+	where
+		Self::Unpinned: 'a,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe {
+			self.as_unpinned_mut_unchecked()
+				.get_key_value_mut(key)
+				.map(|(key, value)| (key, Pin::new_unchecked(value)))
+		}
+	}
+
+	/// Iterates mutably over the entries with keys in `range`, in key order, with pinned values.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range_mut`](`std::collections::BTreeMap::range_mut`),
+	/// iff `range` starts after it ends or starts and ends at the same excluded bound.
+	fn range_pinned_mut<'a, Q, R: RangeBounds<Q>>(
+		&'a mut self,
+		range: R,
+	) -> RangePinnedMut<'a, K, V>
+	where
+		Self::Unpinned: 'a,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe { RangePinnedMut::new(self.as_unpinned_mut_unchecked().range_mut(range)) }
+	}
+
+	/// Iterates over clones of the keys in `range` along with references to their pinned values, in key order.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread in the meantime deadlocks.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`](`std::collections::BTreeMap::range`),
+	/// iff `range` starts after it ends or starts and ends at the same excluded bound.
+	fn range<'a, Q, R: RangeBounds<Q>>(&'a self, range: R) -> RangePinned<'a, K, V>
+	where
+		Self::Unpinned: 'a,
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		unsafe { RangePinned::new(self.as_unpinned().range(range)) }
+	}
+
+	/// Iterates over clones of the keys between `start` and `end` along with references to their pinned values, in key order.
+	///
+	/// This is [`.range(…)`](`PinnedPineMap::range`) with a [`Bound`] pair,
+	/// which is convenient where bounds are built dynamically.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`](`std::collections::BTreeMap::range`),
+	/// iff `start` is after `end` or both are the same excluded bound.
+	fn range_bounds<'a, Q>(&'a self, start: Bound<&Q>, end: Bound<&Q>) -> RangePinned<'a, K, V>
+	where
+		Self::Unpinned: 'a,
+		K: Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		self.range((start, end))
+	}
+
+	/// Iterates mutably over the entries with keys between `start` and `end`, in key order, with pinned values.
+	///
+	/// This is [`.range_pinned_mut(…)`](`PinnedPineMap::range_pinned_mut`) with a [`Bound`] pair,
+	/// which is convenient where bounds are built dynamically.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range_mut`](`std::collections::BTreeMap::range_mut`),
+	/// iff `start` is after `end` or both are the same excluded bound.
+	fn range_bounds_pinned_mut<'a, Q>(
+		&'a mut self,
+		start: Bound<&Q>,
+		end: Bound<&Q>,
+	) -> RangePinnedMut<'a, K, V>
+	where
+		Self::Unpinned: 'a,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.range_pinned_mut((start, end))
+	}
+
+	/// Returns mutable references to the pinned values corresponding to several distinct keys at once.
+	///
+	/// This doesn't require [`V: Unpin`](`Unpin`), so it's possible to link up `!Unpin` values with each other.
+	///
+	/// The keys may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Iff any two of `keys` are equal or, given an inconsistent [`Ord`] implementation,
+	/// find the same (not zero-sized) value.
+	fn get_disjoint_pinned_mut<'a, Q, const N: usize>(
+		&'a mut self,
+		keys: [&Q; N],
+	) -> [Option<Pin<&'a mut V>>; N]
+	where
+		Self::Unpinned: 'a,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe { self.as_unpinned_mut_unchecked() }
+			.get_disjoint_mut(keys)
+			.map(|value| value.map(|value| unsafe { Pin::new_unchecked(value) }))
+	}
+
 	/// Tries to insert a new value produced by the given factory, but only if no such key exists yet.
 	///
 	/// # Errors
@@ -544,6 +1638,97 @@ pub unsafe trait PinnedPineMap<K: Ord, V: ?Sized> {
 	{
 		unsafe { self.as_unpinned_mut_unchecked() }.drop_entry(key)
 	}
+
+	/// Removes a matching entry and moves its value into a new pinned [`Box`].
+	///
+	/// > If `V: Unpin`, use [`.as_unpinned_mut()`](`PinnedPineMap::as_unpinned_mut`)
+	/// > and [`Box::pin`] instead, which is safe.
+	///
+	/// # Safety
+	///
+	/// This moves a pinned value, so `V`'s pinning invariants must allow it to be relocated once,
+	/// for example because the caller knows that nothing observed its address yet.
+	unsafe fn remove_into_pinned_box_unchecked<Q>(&mut self, key: &Q) -> Option<Pin<Box<V>>>
+	where
+		V: Sized,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.as_unpinned_mut_unchecked()
+			.remove_value(key)
+			.map(Box::pin)
+	}
+
+	/// Keeps only the entries for which `f` returns `true`, which may also modify the pinned values it keeps.
+	///
+	/// Removed values are dropped in place, even if some of them panic while being done so.
+	///
+	/// # Returns
+	///
+	/// How many entries were removed.
+	fn retain_update<F: FnMut(&K, Pin<&mut V>) -> bool>(&mut self, mut f: F) -> usize {
+		unsafe { self.as_unpinned_mut_unchecked() }
+			.retain(|key, value| f(key, unsafe { Pin::new_unchecked(value) }))
+	}
+
+	/// Keeps only the first `len` entries in key order.
+	///
+	/// Removed values are dropped in place, even if some of them panic while being done so.
+	fn truncate(&mut self, len: usize) {
+		unsafe { self.as_unpinned_mut_unchecked() }.truncate(len)
+	}
+
+	/// Moves the pinned value corresponding to `old` to the key `new`, without moving the value itself.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Returns
+	///
+	/// The previous key, or [`None`] if there is no entry matching `old` (in which case `new` is dropped).
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `new` already exists, in which case nothing changes.
+	fn replace_key<Q>(&mut self, old: &Q, new: K) -> Result<Option<K>, K>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe { self.as_unpinned_mut_unchecked() }.replace_key(old, new)
+	}
+
+	/// Calls `f` on each entry in key order with the pinned value, stopping at the first error.
+	///
+	/// Changes made to values before that error are kept.
+	///
+	/// # Errors
+	///
+	/// Iff `f` fails, with that error.
+	fn try_for_each_mut<F: FnMut(&K, Pin<&mut V>) -> Result<(), E>, E>(
+		&mut self,
+		mut f: F,
+	) -> Result<(), E> {
+		unsafe { self.as_unpinned_mut_unchecked() }
+			.try_for_each_mut(|key, value| f(key, unsafe { Pin::new_unchecked(value) }))
+	}
+
+	/// Removes a matching key and moves it into `reuse`, so that its resources can be reused for another key.
+	///
+	/// The collection isn't poisoned if dropping the value causes a panic.
+	///
+	/// `reuse` is left unchanged if there is no matching entry.
+	///
+	/// # Returns
+	///
+	/// Whether a matching entry was found.
+	fn remove_key_into<Q>(&mut self, key: &Q, reuse: &mut Option<K>) -> bool
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe { self.as_unpinned_mut_unchecked() }.remove_key_into(key, reuse)
+	}
 }
 
 /// The pinned emplacement API.
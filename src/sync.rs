@@ -1,17 +1,32 @@
 //! Thread-safe b-trees.
 #![allow(clippy::type_complexity)] // For not-quite fallible methods.
 
-use crate::prelude::{
-	PinnedPineMap, PinnedPineMapEmplace, UnpinnedPineMap, UnpinnedPineMapEmplace,
+use crate::{
+	prelude::{PinnedPineMap, PinnedPineMapEmplace, UnpinnedPineMap, UnpinnedPineMapEmplace},
+	Iter, Keys, Range, RangeMut, UnwrapInfallible, Values,
 };
 use bumpalo::Bump;
-use parking_lot::RwLock;
+use parking_lot::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
 use std::{
+	alloc::{self, Layout},
+	any::Any,
+	borrow::Borrow,
 	cell::Cell,
-	collections::BTreeMap,
-	mem::{self, MaybeUninit},
+	cmp::Ordering,
+	collections::{btree_map, BTreeMap, BTreeSet},
+	convert::Infallible,
+	fmt::{self, Debug},
+	hash::{Hash, Hasher},
+	iter::FromIterator,
+	marker::PhantomData,
+	mem::{self, ManuallyDrop, MaybeUninit},
+	ops::{Bound, Deref, DerefMut, RangeBounds},
 	panic::{self, catch_unwind, AssertUnwindSafe},
 	pin::Pin,
+	ptr::{self, NonNull},
+	slice,
+	sync::atomic::{self, AtomicU64, AtomicUsize},
+	thread,
 };
 use tap::{Pipe, TapFallible};
 use this_is_fine::{prelude::*, Fine};
@@ -103,6 +118,7 @@ use this_is_fine::{prelude::*, Fine};
 /// ```
 pub struct PineMap<K: Ord, V> {
 	contents: RwLock<Cambium<K, V>>,
+	len: AtomicUsize,
 }
 
 /// A heterogeneous [`BTreeMap`] that allows pin-projection to its values and additions through shared references, rarely reusing memory.
@@ -140,12 +156,7 @@ pub struct PineMap<K: Ord, V> {
 /// // but it has slightly steeper requirements:
 /// #[derive(Debug)]
 /// struct MyAny;
-/// impl std::borrow::Borrow<dyn Any> for MyAny { //…
-/// #   fn borrow(&self) -> &dyn Any { self }
-/// # }
-/// impl std::borrow::BorrowMut<dyn Any> for MyAny { //…
-/// #   fn borrow_mut(&mut self) -> &mut dyn Any { self }
-/// # }
+/// pinus::borrow_unsized!(MyAny => dyn Any);
 ///
 /// let _: &dyn Any = map
 ///   .emplace(3, MyAny)
@@ -162,17 +173,27 @@ pub struct PineMap<K: Ord, V> {
 /// ```
 pub struct PressedPineMap<K: Ord, V: ?Sized> {
 	contents: RwLock<PressedCambium<K, V>>,
+	len: AtomicUsize,
 }
 
 struct Cambium<K, V> {
 	addresses: BTreeMap<K, *mut V>,
 	memory: Bump,
 	holes: Vec<*mut MaybeUninit<V>>,
+	max_entries: usize,
+	drop_phase: DropPhase,
+	growth_observer: Option<Box<dyn Fn(usize) + Send + Sync>>,
 }
 
 struct PressedCambium<K, V: ?Sized> {
 	addresses: BTreeMap<K, *mut V>,
 	memory: Bump,
+	// Whether any value that was added since the last clear may need dropping,
+	// as `mem::needs_drop::<V>()` isn't available for unsized `V`.
+	values_need_drop: bool,
+	drop_phase: DropPhase,
+	// Unique across instances, and replaced whenever entries may have been removed.
+	generation: u64,
 	// We can't determine (cross-architecture) if we actually own the value pointers,
 	// because pointer comparisons not from within the same allocation aren't meaningful,
 	// so we can't derive holes on value removal.
@@ -191,58 +212,2412 @@ impl<K: Ord, V> PineMap<K, V> {
 				addresses: BTreeMap::new(),
 				memory: Bump::new(),
 				holes: Vec::new(),
+				max_entries: usize::MAX,
+				drop_phase: DropPhase::Interleaved,
+				growth_observer: None,
 			}),
+			len: AtomicUsize::new(0),
 		}
 	}
 
 	/// Creates a new empty [`PineMap`] that will store values contiguously
 	/// until `capacity` (in concurrently live entries) is exceeded.
+	///
+	/// # Panics
+	///
+	/// Iff the required size in bytes overflows (see [`PineMap::try_with_capacity`] for a fallible version).
 	#[must_use]
 	pub fn with_capacity(capacity: usize) -> Self {
+		let layout = Layout::array::<V>(capacity).expect("`PineMap` capacity overflow");
+		Self {
+			contents: RwLock::new(Cambium {
+				addresses: BTreeMap::new(),
+				memory: Bump::with_capacity(layout.size()),
+				holes: Vec::new(),
+				max_entries: usize::MAX,
+				drop_phase: DropPhase::Interleaved,
+				growth_observer: None,
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Creates a new empty [`PineMap`] that will store values contiguously
+	/// until `capacity` (in concurrently live entries) is exceeded.
+	///
+	/// # Errors
+	///
+	/// Iff the required size in bytes overflows or the allocation fails.
+	pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+		let layout = Layout::array::<V>(capacity).map_err(|_| TryReserveError::CapacityOverflow)?;
+		Ok(Self {
+			contents: RwLock::new(Cambium {
+				addresses: BTreeMap::new(),
+				memory: Bump::try_with_capacity(layout.size())
+					.map_err(|_| TryReserveError::AllocError)?,
+				holes: Vec::new(),
+				max_entries: usize::MAX,
+				drop_phase: DropPhase::Interleaved,
+				growth_observer: None,
+			}),
+			len: AtomicUsize::new(0),
+		})
+	}
+
+	/// Creates a new empty [`PineMap`] that stores its values in `arena`.
+	///
+	/// The instance takes ownership of `arena` and [resets](`Bump::reset`) it right away,
+	/// which keeps only its last allocated chunk. It's reset again whenever the instance is [cleared](`UnpinnedPineMap::clear`).
+	#[must_use]
+	pub fn new_with_arena(mut arena: Bump) -> Self {
+		arena.reset();
+		Self {
+			contents: RwLock::new(Cambium {
+				addresses: BTreeMap::new(),
+				memory: arena,
+				holes: Vec::new(),
+				max_entries: usize::MAX,
+				drop_phase: DropPhase::Interleaved,
+				growth_observer: None,
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Creates a new empty [`PineMap`] that holds at most `max_entries` entries at once.
+	///
+	/// Use [`.insert_bounded(…)`](`PineMap::insert_bounded`) or [`.try_emplace_with_bounded(…)`](`PineMap::try_emplace_with_bounded`)
+	/// to insert into a full instance gracefully, with a [`CapacityExceeded`] error.
+	///
+	/// # Panics
+	///
+	/// As a fallback, all other methods that may insert (including those from [`UnpinnedPineMap`], [`UnpinnedPineMapEmplace`],
+	/// [`PinnedPineMap`] and [`PinnedPineMapEmplace`], and [`Extend::extend`])
+	/// panic (without inserting) if they would add a new entry to the resulting instance while `max_entries` are present already.
+	/// Their documentation doesn't repeat this.
+	#[must_use]
+	pub fn with_max_entries(max_entries: usize) -> Self {
+		Self {
+			contents: RwLock::new(Cambium {
+				addresses: BTreeMap::new(),
+				memory: Bump::new(),
+				holes: Vec::new(),
+				max_entries,
+				drop_phase: DropPhase::Interleaved,
+				growth_observer: None,
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Creates a new empty [`PineMap`] that drops its entries in the given [`DropPhase`]
+	/// when it's [cleared](`UnpinnedPineMap::clear`) or dropped.
+	#[must_use]
+	pub fn with_drop_phase(drop_phase: DropPhase) -> Self {
 		Self {
 			contents: RwLock::new(Cambium {
 				addresses: BTreeMap::new(),
-				memory: Bump::with_capacity(mem::size_of::<V>() * capacity),
+				memory: Bump::new(),
 				holes: Vec::new(),
+				max_entries: usize::MAX,
+				drop_phase,
+				growth_observer: None,
 			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Inserts a new value, but only if no such key exists yet and the entry limit set through
+	/// [`PineMap::with_max_entries`] isn't reached.
+	///
+	/// # Errors
+	///
+	/// Outer error: Iff the key is new but the instance is full.
+	///
+	/// Inner error: Iff an entry matching `key` already exists.
+	#[allow(clippy::missing_panics_doc)] // Only unreachable panics.
+	pub fn insert_bounded(
+		&self,
+		key: K,
+		value: V,
+	) -> Result<Fine<&V, (K, V)>, CapacityExceeded<K, V>> {
+		let mut contents = self.write_counted();
+		if contents.is_full() && !contents.addresses.contains_key(&key) {
+			return Err(CapacityExceeded { key, value });
+		}
+		let value = Cell::new(Some(value));
+		contents
+			.try_emplace_with(key, |_, slot| {
+				Ok::<_, Infallible>(slot.write(value.take().expect("unreachable")))
+			})
+			.unwrap_infallible()
+			.map(|value| unsafe { &*value })
+			.map_err(|(key, _)| (key, value.take().expect("unreachable")))
+			.pipe(Ok)
+	}
+
+	/// Tries to emplace a new value produced by the given factory, but only if no such key exists yet and the entry limit set through
+	/// [`PineMap::with_max_entries`] isn't reached.
+	///
+	/// # Errors
+	///
+	/// Outer error: Iff the key is new but the instance is full, in which case the factory isn't called.
+	///
+	/// Middle error: Iff `value_factory` fails.
+	///
+	/// Inner error: Iff an entry matching `key` already exists.
+	pub fn try_emplace_with_bounded<
+		F: for<'a> FnOnce(&K, &'a mut MaybeUninit<V>) -> Result<&'a mut V, E>,
+		E,
+	>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Result<Result<Fine<&V, (K, F)>, E>, CapacityExceeded<K, F>> {
+		let mut contents = self.write_counted();
+		if contents.is_full() && !contents.addresses.contains_key(&key) {
+			return Err(CapacityExceeded {
+				key,
+				value: value_factory,
+			});
+		}
+		contents
+			.try_emplace_with(key, value_factory)
+			.map(|inserted| inserted.map(|value| unsafe { &*value }))
+			.pipe(Ok)
+	}
+
+	/// Inserts a new value, but only if no such key exists yet, taking the key out of its [`Box`] only if it's new.
+	///
+	/// This avoids moving large keys around when the entry already exists.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	pub fn insert_boxed(&self, key: Box<K>, value: V) -> Fine<&V, (Box<K>, V)> {
+		let mut contents = self.write_counted();
+		if let Some(existing_value) = contents.addresses.get(&*key) {
+			return (unsafe { &**existing_value }, Err((key, value)));
+		}
+		let (value, _) = contents
+			.try_emplace_with(*key, |_, slot| Ok::<_, Infallible>(slot.write(value)))
+			.unwrap_infallible();
+		(unsafe { &*value }, Ok(()))
+	}
+
+	/// Tries to insert a new value produced by the given factory, but only if no such key exists yet,
+	/// and reports whether this allocated a new arena chunk.
+	///
+	/// That's where allocation latency spikes can occur, so this can be used to log them or to pre-warm the instance.
+	///
+	/// # Errors
+	///
+	/// Outer error: Iff `value_factory` fails.
+	///
+	/// Inner error: Iff an entry matching `key` already exists.
+	#[allow(clippy::missing_panics_doc)] // Only unreachable panics.
+	pub fn try_insert_with_growth<F: FnOnce(&K) -> Result<V, E>, E>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Result<Fine<(&V, bool), (K, F)>, E> {
+		let mut contents = self.write_counted();
+		// Only counts chunks and doesn't read any memory.
+		let chunks_before = unsafe { contents.memory.iter_allocated_chunks_raw() }.count();
+		let value_factory = Cell::new(Some(value_factory));
+		let (value, result) = contents.try_emplace_with(key, |key, slot| {
+			slot.write(value_factory.take().expect("unreachable")(key)?)
+				.pipe(Ok)
+		})?;
+		let value = unsafe { &*value };
+		let grew = unsafe { contents.memory.iter_allocated_chunks_raw() }.count() != chunks_before;
+		(
+			(value, grew),
+			result.map_err(|(key, _)| (key, value_factory.take().expect("unreachable"))),
+		)
+			.pipe(Ok)
+	}
+
+	/// Inserts a new value produced by the given factory, but only if no such key exists yet,
+	/// and returns the entry's position in key order along with the value reference.
+	///
+	/// The position is determined under the same lock acquisition as the insertion.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists, in which case its position is returned.
+	#[allow(clippy::missing_panics_doc)] // Only unreachable panics.
+	pub fn insert_with_index<F: FnOnce(&K) -> V>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Fine<(usize, &V), (K, F)> {
+		let mut contents = self.write_counted();
+		let index = contents.addresses.range(..&key).count();
+		let value_factory = Cell::new(Some(value_factory));
+		contents
+			.try_emplace_with(key, |key, slot| {
+				Ok::<_, Infallible>(slot.write(value_factory.take().expect("unreachable")(key)))
+			})
+			.unwrap_infallible()
+			.map(|value| (index, unsafe { &*value }))
+			.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
+	}
+
+	/// Inserts copies of each of `entries` whose key isn't present yet.
+	///
+	/// Entries are processed in order, so the first of several entries with equal keys wins.
+	///
+	/// Memory for all of `entries` is reserved at once up front.
+	/// Slots that end up unused (due to duplicate keys) are reused by later insertions.
+	pub fn extend_from_slice(&mut self, entries: &[(K, V)])
+	where
+		K: Copy,
+		V: Copy,
+	{
+		let mut contents = self.get_mut_counted();
+		contents.reserve_holes(entries.len());
+		for &(key, value) in entries {
+			let _ =
+				contents.try_emplace_with(key, |_, slot| Ok::<_, Infallible>(slot.write(value)));
+		}
+	}
+
+	/// Inserts all of `entries`, or none of them.
+	///
+	/// Since this takes an exclusive reference, the keys are checked before anything is inserted,
+	/// so there is never anything to roll back and no memory is reserved in the error case.
+	///
+	/// # Errors
+	///
+	/// Iff any key is already present or appears more than once in `entries`.
+	/// This instance is then left exactly as it was and all of `entries` are returned in their original order.
+	///
+	/// # Panics
+	///
+	/// Iff inserting all of `entries` would exceed this instance's `max_entries`, before inserting any of them.
+	pub fn insert_all_or_rollback<I: IntoIterator<Item = (K, V)>>(
+		&mut self,
+		entries: I,
+	) -> Result<(), Vec<(K, V)>> {
+		let entries = entries.into_iter().collect::<Vec<_>>();
+		let mut contents = self.get_mut_counted();
+
+		let mut batch_keys = BTreeSet::new();
+		if entries
+			.iter()
+			.any(|(key, _)| contents.addresses.contains_key(key) || !batch_keys.insert(key))
+		{
+			return Err(entries);
+		}
+		drop(batch_keys);
+
+		assert!(
+			entries.len()
+				<= contents
+					.max_entries
+					.saturating_sub(contents.addresses.len()),
+			"Tried to add an entry beyond the `PineMap`'s `max_entries`."
+		);
+		contents.reserve_holes(entries.len());
+		for (key, value) in entries {
+			// Can't conflict, as checked above.
+			let _ =
+				contents.try_emplace_with(key, |_, slot| Ok::<_, Infallible>(slot.write(value)));
+		}
+		Ok(())
+	}
+
+	/// Returns all values as one slice, but only if they are stored contiguously.
+	///
+	/// This is typically the case after inserting at most `capacity` entries into an instance created with
+	/// [`PineMap::with_capacity`], without any removals.
+	///
+	/// **The slice is in memory order, which is unrelated to key order.**
+	pub fn try_values_as_slice(&self) -> Option<&[V]> {
+		let contents = self.contents.read(/* poisoned */);
+		let len = contents.addresses.len();
+		if mem::size_of::<V>() == 0 || len == 0 {
+			return Some(unsafe { slice::from_raw_parts(NonNull::dangling().as_ptr(), len) });
+		}
+
+		let mut values = contents.addresses.values().copied().collect::<Vec<_>>();
+		values.sort_unstable();
+		if !values
+			.windows(2)
+			.all(|pair| pair[1] == pair[0].wrapping_add(1))
+		{
+			return None;
+		}
+
+		// The value pointers are only valid for their respective values,
+		// so the slice must be derived from the containing chunk instead.
+		let start = values[0] as usize;
+		let end = values[len - 1].wrapping_add(1) as usize;
+		for (chunk, chunk_len) in unsafe { contents.memory.iter_allocated_chunks_raw() } {
+			let chunk_start = chunk as usize;
+			if chunk_start <= start && end <= chunk_start + chunk_len {
+				return Some(unsafe {
+					slice::from_raw_parts(chunk.add(start - chunk_start).cast::<V>(), len)
+				});
+			}
 		}
+		None
+	}
+
+	/// Returns pointers to all values, in key order, as captured under a single brief lock acquisition.
+	///
+	/// The pointers stay valid for reads until the respective entry is removed or the instance is [shrunk](`PineMap::shrink_to`), cleared or dropped,
+	/// which requires exclusive access. They must not be written through while the instance is shared.
+	pub fn value_ptrs(&self) -> Vec<NonNull<V>> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.map(|value| unsafe { NonNull::new_unchecked(*value) })
+			.collect()
+	}
+
+	/// Returns how many bytes of the backing arena are in use, including for holes and padding.
+	pub fn allocated_bytes(&self) -> usize {
+		self.contents.read(/* poisoned */).memory.allocated_bytes()
+	}
+
+	/// Estimates the instance's total heap footprint in bytes:
+	/// the [`allocated_bytes`](`PineMap::allocated_bytes`), the list of holes and the key index.
+	///
+	/// The index's node layout isn't public, so its share is only a rough estimate
+	/// that assumes nodes are about two thirds full.
+	pub fn total_memory_estimate(&self) -> usize {
+		let contents = self.contents.read(/* poisoned */);
+		contents.memory.allocated_bytes()
+			+ contents.holes.capacity() * mem::size_of::<*mut V>()
+			+ estimate_index_bytes::<K, V>(contents.addresses.len())
+	}
+
+	/// Sets a callback that's called with the new [`allocated_bytes`](`PineMap::allocated_bytes`)
+	/// whenever the arena allocates a fresh chunk for new slots, replacing any previous one.
+	///
+	/// This is meant to help with sizing [`PineMap::with_capacity`] empirically.
+	///
+	/// The callback runs while the instance is write-locked, so calling into the instance from it deadlocks.
+	pub fn set_growth_observer(&mut self, f: Box<dyn Fn(usize) + Send + Sync>) {
+		self.contents.get_mut(/* poisoned */).growth_observer = Some(f);
+	}
+
+	/// Tops up the free slots to at least `count`, so that the next `count` insertions don't allocate.
+	///
+	/// Unlike [`PineMap::with_capacity`], which reserves memory once up front,
+	/// this is meant to be called repeatedly to maintain a pool of slots, e.g. between iterations of a real-time loop.
+	/// Existing holes count towards `count`, and the missing slots are allocated contiguously.
+	pub fn reserve_holes(&mut self, count: usize) {
+		self.contents.get_mut(/* poisoned */).reserve_holes(count);
+	}
+
+	/// Returns the number of free slots that will be reused before more memory is allocated for values.
+	pub fn hole_count(&self) -> usize {
+		self.contents.read(/* poisoned */).holes.len()
+	}
+
+	/// Removes and drops all entries with keys in `range`, even if some of them panic while being dropped,
+	/// and returns how many there were.
+	///
+	/// Freed slots that (together with earlier ones) make up the arena's most recent allocations are released right away,
+	/// which rewinds the arena. Other freed slots are reused by later insertions.
+	/// If this removes the last entries, the arena is [reset](`Bump::reset`) instead.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn remove_range<Q, R: RangeBounds<Q>>(&mut self, range: R) -> usize
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let drain = self.drain_range(range);
+		let count = drain.size_hint().0;
+		// Dropping the iterator drops the values in place.
+		drop(drain);
+
+		let contents = self.contents.get_mut(/* poisoned */);
+		if contents.addresses.is_empty() {
+			contents.holes.clear();
+			contents.memory.reset();
+		} else {
+			contents.rewind_holes();
+		}
+		count
+	}
+
+	/// Removes all entries and returns them as owned pairs, in key order, without dropping any of them.
+	///
+	/// Like [`.clear()`](`UnpinnedPineMap::clear`), this resets the backing arena.
+	pub fn take_all(&mut self) -> Vec<(K, V)> {
+		let mut contents = self.get_mut_counted();
+
+		// Allocating first means nothing can panic once values are being moved out.
+		let mut pairs = Vec::with_capacity(contents.addresses.len());
+		pairs.extend(
+			mem::take(&mut contents.addresses)
+				.into_iter()
+				.map(|(key, value)| (key, unsafe { value.read() })),
+		);
+
+		contents.holes.clear();
+		contents.memory.reset();
+		pairs
+	}
+
+	/// Consumes the instance and returns its entries as owned pairs, in key order.
+	///
+	/// This is [`.take_all()`](`PineMap::take_all`), but also frees the backing arena right away.
+	#[must_use]
+	pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+		self.take_all()
+	}
+
+	/// Removes all entries with keys in `range` and returns them as owned pairs, in key order.
+	///
+	/// The freed slots are reused by later insertions.
+	///
+	/// Entries in `range` that haven't been yielded when the iterator is dropped are removed and dropped then,
+	/// even if some of them panic while being done so (as with [`.clear()`](`UnpinnedPineMap::clear`)).
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn drain_range<Q, R: RangeBounds<Q>>(&mut self, range: R) -> DrainRange<'_, K, V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let mut contents = self.get_mut_counted();
+		let addresses = &mut contents.addresses;
+
+		// WAITING ON: <https://github.com/rust-lang/rust/issues/70530> (`BTreeMap::drain_filter`)
+		let mut drained = match range.start_bound() {
+			Bound::Included(start) => addresses.split_off(start),
+			Bound::Excluded(start) => {
+				let mut drained = addresses.split_off(start);
+				if let Some((key, value)) = drained.remove_entry(start) {
+					addresses.insert(key, value);
+				}
+				drained
+			}
+			Bound::Unbounded => mem::take(addresses),
+		};
+		let mut rest = match range.end_bound() {
+			Bound::Included(end) => {
+				let mut rest = drained.split_off(end);
+				if let Some((key, value)) = rest.remove_entry(end) {
+					drained.insert(key, value);
+				}
+				rest
+			}
+			Bound::Excluded(end) => drained.split_off(end),
+			Bound::Unbounded => BTreeMap::new(),
+		};
+		addresses.append(&mut rest);
+
+		DrainRange {
+			contents,
+			entries: drained.into_iter(),
+		}
+	}
+
+	/// Moves all values into a fresh arena with room for `min_entries` (or more, if there are more entries),
+	/// releasing the previous arena's memory.
+	///
+	/// This is an exception to values otherwise staying in place until they are removed,
+	/// so addresses previously returned by [`.stable_address(…)`](`UnpinnedPineMap::stable_address`) become outdated.
+	/// (Like all exclusive methods that move values, this isn't available on pinned instances.)
+	///
+	/// # Panics
+	///
+	/// Iff the required size in bytes overflows.
+	pub fn shrink_to(&mut self, min_entries: usize) {
+		let mut counted = self.get_mut_counted();
+		let contents = &mut *counted;
+
+		let len = contents.addresses.len();
+		let memory = Bump::with_capacity(
+			mem::size_of::<V>()
+				.checked_mul(len.max(min_entries))
+				.expect("`PineMap` capacity overflow"),
+		);
+		// Allocated up front so that nothing can panic once values are being moved.
+		let slots = memory.alloc_slice_fill_with(len, |_| MaybeUninit::<V>::uninit());
+		for (value, slot) in contents.addresses.values_mut().zip(slots) {
+			unsafe { value.copy_to_nonoverlapping(slot.as_mut_ptr(), 1) };
+			*value = slot.as_mut_ptr();
+		}
+
+		contents.holes.clear();
+		contents.memory = memory;
+	}
+
+	/// Like [`.retain(…)`](`UnpinnedPineMap::retain`), but afterwards [shrinks](`PineMap::shrink_to`) the arena
+	/// if there are more holes than live entries.
+	///
+	/// This amortises compaction into eviction passes that may remove most entries.
+	/// For a different threshold, call [`.retain(…)`](`UnpinnedPineMap::retain`) and [`.shrink_to(…)`](`PineMap::shrink_to`) directly.
+	///
+	/// # Returns
+	///
+	/// How many entries were removed.
+	pub fn retain_and_compact<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F) -> usize {
+		let removed = self.retain(f);
+		let contents = self.contents.get_mut(/* poisoned */);
+		if contents.holes.len() > contents.addresses.len() {
+			self.shrink_to(0);
+		}
+		removed
+	}
+
+	/// Returns the values with keys in `range` as one mutable slice, but only if they are stored contiguously in key order.
+	///
+	/// This is typically the case for a key window of an instance filled in ascending key order through [`Extend`]
+	/// or after [`.reserve_holes(…)`](`PineMap::reserve_holes`), without any removals.
+	/// (Plain insertions into the arena are placed at descending addresses.)
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	///
+	/// # Panics
+	///
+	/// Like [`BTreeMap::range`], iff `range` starts after it ends or starts and ends at the same excluded bound.
+	pub fn try_range_as_mut_slice<Q, R: RangeBounds<Q>>(&mut self, range: R) -> Option<&mut [V]>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.get_mut(/* poisoned */);
+		let values = contents
+			.addresses
+			.range(range)
+			.map(|(_, value)| *value)
+			.collect::<Vec<_>>();
+		let len = values.len();
+		if mem::size_of::<V>() == 0 || len == 0 {
+			return Some(unsafe { slice::from_raw_parts_mut(NonNull::dangling().as_ptr(), len) });
+		}
+
+		if !values
+			.windows(2)
+			.all(|pair| pair[1] == pair[0].wrapping_add(1))
+		{
+			return None;
+		}
+
+		// As in `.try_values_as_slice()`, the slice must be derived from the containing chunk.
+		let start = values[0] as usize;
+		let end = values[len - 1].wrapping_add(1) as usize;
+		for (chunk, chunk_len) in unsafe { contents.memory.iter_allocated_chunks_raw() } {
+			let chunk_start = chunk as usize;
+			if chunk_start <= start && end <= chunk_start + chunk_len {
+				return Some(unsafe {
+					slice::from_raw_parts_mut(chunk.add(start - chunk_start).cast::<V>(), len)
+				});
+			}
+		}
+		None
+	}
+
+	/// Collects references to all entries in key order, under a single brief lock acquisition.
+	///
+	/// Unlike with [`.keys()`](`UnpinnedPineMap::keys`) or [`.values()`](`UnpinnedPineMap::values`),
+	/// the instance isn't locked while the references are in use and keys aren't cloned.
+	///
+	/// # Safety
+	///
+	/// The index may relocate keys whenever an entry is inserted,
+	/// so **the instance must not be inserted into** (through any method) while any of the returned *key* references are in use.
+	///
+	/// The value references are unaffected by this, as values stay in place until removed, which requires exclusive access.
+	pub unsafe fn ref_snapshot_unchecked(&self) -> Vec<(&K, &V)> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.iter()
+			.map(|(key, value)| (&*(key as *const K), &**value))
+			.collect()
+	}
+
+	/// Creates a defragmented copy of this instance, without holes and with all values stored contiguously.
+	///
+	/// This only needs a shared reference, so the original remains usable until it's replaced.
+	#[must_use]
+	pub fn compact_into(&self) -> PineMap<K, V>
+	where
+		K: Clone,
+		V: Clone,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		let mut compacted = PineMap::with_capacity(contents.addresses.len());
+		{
+			let mut target = compacted.get_mut_counted();
+			// `self` respects its own limit, so copying its entries can't run into it.
+			target.max_entries = contents.max_entries;
+			target.drop_phase = contents.drop_phase;
+			for (key, value) in &contents.addresses {
+				let value = unsafe { &**value };
+				let _ = target.try_emplace_with(key.clone(), |_, slot| {
+					Ok::<_, Infallible>(slot.write(value.clone()))
+				});
+			}
+		}
+		compacted
+	}
+
+	/// Looks up `key`, for in-place insertion if it's absent.
+	///
+	/// Returning a [`VacantEntry`] holds the write lock until that entry is consumed or dropped.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned.
+	///
+	/// Calling any other method of this instance while a [`VacantEntry`] is alive on the same thread deadlocks.
+	pub fn entry(&self, key: K) -> Entry<'_, K, V> {
+		let contents = self.write_counted();
+		if let Some(value) = contents.addresses.get(&key) {
+			Entry::Occupied(OccupiedEntry {
+				key,
+				value: unsafe { &**value },
+			})
+		} else {
+			Entry::Vacant(VacantEntry { contents, key })
+		}
+	}
+
+	/// Looks up `key`, for mutable access to its value after inserting one if it's absent.
+	///
+	/// This is the exclusive counterpart to [`.entry(…)`](`PineMap::entry`),
+	/// which inserts through a shared reference but only gives out shared references to existing values.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned.
+	pub fn entry_mut(&mut self, key: K) -> EntryMut<'_, K, V> {
+		EntryMut {
+			contents: self.get_mut_counted(),
+			key,
+		}
+	}
+
+	/// Looks up `key` in a pinned instance, for mutable access to its value after inserting one if it's absent.
+	///
+	/// This is an associated function (like [`Box::leak`]) as `self: &mut Pin<Self>` isn't a valid receiver yet.
+	pub fn pinned_entry(this: &mut Pin<Self>, key: K) -> PinnedEntry<'_, K, V> {
+		PinnedEntry {
+			contents: unsafe { this.as_unpinned_mut_unchecked() }.get_mut_counted(),
+			key,
+		}
+	}
+
+	/// Lazily removes the entries whose key matches `pred`, drops their values in place and yields their keys, in key order.
+	///
+	/// The freed slots are reused by later insertions.
+	///
+	/// Entries that haven't been visited when the iterator is dropped are still checked then, unless that happens during a panic.
+	/// If the iterator is leaked instead, they remain in the map.
+	///
+	/// Use [`PineMap::pinned_extract_keys_if`] on pinned instances.
+	pub fn extract_keys_if<F: FnMut(&K) -> bool>(&mut self, pred: F) -> ExtractKeysIf<'_, K, V, F> {
+		let contents = self.get_mut_counted();
+		let next_key = contents.addresses.keys().next().map(|key| key as *const K);
+		ExtractKeysIf {
+			contents,
+			next_key,
+			pred,
+		}
+	}
+
+	/// Lazily removes the entries of a pinned instance whose key matches `pred`, drops their values in place and yields their keys, in key order.
+	///
+	/// This is [`.extract_keys_if(…)`](`PineMap::extract_keys_if`), which never moves values.
+	/// It's an associated function (like [`Box::leak`]) as `self: &mut Pin<Self>` isn't a valid receiver yet.
+	pub fn pinned_extract_keys_if<F: FnMut(&K) -> bool>(
+		this: &mut Pin<Self>,
+		pred: F,
+	) -> ExtractKeysIf<'_, K, V, F> {
+		unsafe { this.as_unpinned_mut_unchecked() }.extract_keys_if(pred)
+	}
+
+	/// Acquires the write lock once for a batch of shared-reference operations.
+	///
+	/// Note that this is **not** a way to get at `&mut V` through a shared reference:
+	/// Other value references handed out through `&self` may be live, so the guard only inserts and reads.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned.
+	///
+	/// Calling any other method of this instance while the guard is alive on the same thread deadlocks.
+	pub fn write(&self) -> WriteGuard<'_, K, V> {
+		WriteGuard {
+			contents: self.write_counted(),
+		}
+	}
+
+	/// Acquires an upgradable read lock, which can later be turned into a [`WriteGuard`] without releasing it in between.
+	///
+	/// This allows read-then-conditionally-write transactions that no other writer can interleave with.
+	/// Other readers aren't blocked, but only one upgradable read lock can be held at a time.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned.
+	///
+	/// Calling any mutating method of this instance while the guard is alive on the same thread deadlocks.
+	pub fn upgradable_read(&self) -> UpgradableReadGuard<'_, K, V> {
+		UpgradableReadGuard {
+			contents: self.contents.upgradable_read(/* poisoned */),
+			len: &self.len,
+		}
+	}
+
+	/// Runs `f` with a [`WriteGuard`] that doesn't reuse holes,
+	/// so that values inserted through it are placed contiguously while the arena's current chunk has room.
+	///
+	/// Hole reuse resumes afterwards, even if `f` panics.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned or `f` panics.
+	///
+	/// Calling any other method of this instance from `f` deadlocks.
+	pub fn contiguous_batch<R, F: FnOnce(&mut WriteGuard<'_, K, V>) -> R>(&self, f: F) -> R {
+		let mut guard = self.write();
+
+		// Holes created during the batch are at its end, so reusing them there is fine.
+		let holes = mem::take(&mut guard.contents.holes);
+		let result = catch_unwind(AssertUnwindSafe(|| f(&mut guard)));
+		guard.contents.holes.extend(holes);
+
+		result.unwrap_or_else(|panic| panic::resume_unwind(panic))
+	}
+
+	/// Returns the number of entries without locking.
+	///
+	/// This reads a counter with [`Relaxed`](`atomic::Ordering::Relaxed`) ordering,
+	/// so it's only exact while no other thread is inserting (or once such insertions are otherwise synchronised with).
+	/// Use [`.len()`](`UnpinnedPineMap::len`) for an exact count.
+	pub fn len_relaxed(&self) -> usize {
+		self.len.load(atomic::Ordering::Relaxed)
+	}
+
+	/// Like [`.clear()`](`UnpinnedPineMap::clear`), but drops the entries in parallel on [`rayon`]'s global thread pool.
+	///
+	/// This is worthwhile only for large instances with expensive [`Drop`] implementations.
+	/// [`DropPhase::Interleaved`] drops each key right before its value on the same thread,
+	/// while the other phases finish all drops of one kind before starting the other.
+	///
+	/// # Panics
+	///
+	/// Iff any key or value panics while being dropped, after all other entries were dropped and the backing memory was reset.
+	/// (As with [`.clear()`](`UnpinnedPineMap::clear`), this instance is empty and not poisoned afterwards.)
+	///
+	/// A single panic is resumed as-is. Several are collected into a [`Vec`]`<`[`Box`]`<dyn `[`Any`]` + `[`Send`]`>>`, which is resumed instead.
+	#[cfg(feature = "rayon")]
+	pub fn par_clear(&mut self)
+	where
+		K: Send,
+		V: Send,
+	{
+		let mut contents = self.get_mut_counted();
+
+		// See `.clear()`.
+		contents.holes.clear();
+
+		let drop_phase = contents.drop_phase;
+		let addresses = mem::take(&mut contents.addresses);
+		// Keys may panic while being dropped too, so the arena must be reset regardless.
+		let success = catch_unwind(AssertUnwindSafe(|| {
+			if mem::needs_drop::<V>() {
+				par_drop_all(addresses, drop_phase)
+			} else {
+				drop(addresses)
+			}
+		}));
+
+		contents.memory.reset();
+
+		success.unwrap_or_else(|panic| panic::resume_unwind(panic));
+	}
+
+	/// Verifies this instance's internal invariants, as a debugging aid.
+	///
+	/// This takes the write lock and checks that:
+	///
+	/// - the entry count matches [`.len()`](`UnpinnedPineMap::len`),
+	/// - every value pointer is non-null and aligned,
+	/// - no two live values share an address,
+	/// - no hole coincides with a live value and
+	/// - `holes` contains no duplicates.
+	///
+	/// Values aren't required to lie within the backing arena,
+	/// as a value factory may place them elsewhere.
+	///
+	/// # Panics
+	///
+	/// Iff an invariant is violated, with a descriptive message, or the instance was poisoned.
+	///
+	/// Calling this while a [`VacantEntry`] is alive on the same thread deadlocks.
+	#[cfg(feature = "debug-audit")]
+	pub fn audit(&self) {
+		let contents = self.contents.write(/* poisoned */);
+
+		let len = self.len.load(atomic::Ordering::Relaxed);
+		assert_eq!(
+			contents.addresses.len(),
+			len,
+			"`PineMap` audit: {} entries, but `.len()` is {}",
+			contents.addresses.len(),
+			len,
+		);
+
+		let mut values = Vec::with_capacity(contents.addresses.len());
+		for (index, &value) in contents.addresses.values().enumerate() {
+			assert!(
+				!value.is_null(),
+				"`PineMap` audit: the value at index {} is null",
+				index,
+			);
+			assert!(
+				value as usize & (mem::align_of::<V>() - 1) == 0,
+				"`PineMap` audit: the value at index {} ({:p}) is misaligned",
+				index,
+				value,
+			);
+			values.push(value);
+		}
+
+		// Zero-sized values may all share one dangling address.
+		if mem::size_of::<V>() == 0 {
+			return;
+		}
+
+		values.sort_unstable();
+		if let Some(&[shared, _]) = values
+			.windows(2)
+			.find(|pair| matches!(pair, [a, b] if a == b))
+		{
+			panic!(
+				"`PineMap` audit: two live values share the address {:p}",
+				shared
+			);
+		}
+
+		let mut holes = contents
+			.holes
+			.iter()
+			.map(|&hole| hole.cast::<V>())
+			.collect::<Vec<_>>();
+		holes.sort_unstable();
+		if let Some(&[duplicate, _]) = holes
+			.windows(2)
+			.find(|pair| matches!(pair, [a, b] if a == b))
+		{
+			panic!(
+				"`PineMap` audit: the hole {:p} is listed more than once",
+				duplicate
+			);
+		}
+		if let Some(hole) = holes.iter().find(|hole| values.binary_search(hole).is_ok()) {
+			panic!("`PineMap` audit: the hole {:p} is also a live value", *hole);
+		}
+	}
+
+	fn write_counted(&self) -> Counted<'_, RwLockWriteGuard<'_, Cambium<K, V>>> {
+		Counted {
+			contents: self.contents.write(/* poisoned */),
+			len: &self.len,
+		}
+	}
+
+	fn get_mut_counted(&mut self) -> Counted<'_, &mut Cambium<K, V>> {
+		Counted {
+			contents: self.contents.get_mut(/* poisoned */),
+			len: &self.len,
+		}
+	}
+}
+
+impl<K: Ord, V: ?Sized> PressedPineMap<K, V> {
+	/// Creates a new empty [`PressedPineMap`].
+	#[must_use]
+	pub fn new() -> Self {
+		Self {
+			contents: RwLock::new(PressedCambium {
+				addresses: BTreeMap::new(),
+				memory: Bump::new(),
+				values_need_drop: false,
+				drop_phase: DropPhase::Interleaved,
+				generation: next_generation(),
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Creates a new empty [`PressedPineMap`] that will store values (almost) contiguously
+	/// until `capacity_bytes` plain bytes are exceeded.
+	///
+	/// Padding between values of different alignments counts towards this.
+	/// The arena may round its first chunk up somewhat, to suit the global allocator.
+	#[must_use]
+	pub fn with_capacity(capacity_bytes: usize) -> Self {
+		Self {
+			contents: RwLock::new(PressedCambium {
+				addresses: BTreeMap::new(),
+				memory: Bump::with_capacity(capacity_bytes),
+				values_need_drop: false,
+				drop_phase: DropPhase::Interleaved,
+				generation: next_generation(),
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Creates a new empty [`PressedPineMap`] that stores its values in `arena`.
+	///
+	/// The instance takes ownership of `arena` and [resets](`Bump::reset`) it right away,
+	/// which keeps only its last allocated chunk. It's reset again whenever the instance is [cleared](`UnpinnedPineMap::clear`).
+	#[must_use]
+	pub fn new_with_arena(mut arena: Bump) -> Self {
+		arena.reset();
+		Self {
+			contents: RwLock::new(PressedCambium {
+				addresses: BTreeMap::new(),
+				memory: arena,
+				values_need_drop: false,
+				drop_phase: DropPhase::Interleaved,
+				generation: next_generation(),
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Creates a new empty [`PressedPineMap`] that drops its entries in the given [`DropPhase`]
+	/// when it's [cleared](`UnpinnedPineMap::clear`) or dropped.
+	#[must_use]
+	pub fn with_drop_phase(drop_phase: DropPhase) -> Self {
+		Self {
+			contents: RwLock::new(PressedCambium {
+				addresses: BTreeMap::new(),
+				memory: Bump::new(),
+				values_need_drop: false,
+				drop_phase,
+				generation: next_generation(),
+			}),
+			len: AtomicUsize::new(0),
+		}
+	}
+
+	/// Iterates over the entries for which `f` projects the value to `Some`, in key order,
+	/// yielding the projections instead of the values.
+	///
+	/// This generalises [`.iter_downcast()`](`PressedPineMap::iter_downcast`) to any projection, e.g. through a trait's `.as_any()`.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread meanwhile deadlocks.
+	pub fn filter_map_values<'a, T: 'a + ?Sized, F: 'a + FnMut(&V) -> Option<&T>>(
+		&'a self,
+		mut f: F,
+	) -> impl 'a + Iterator<Item = (K, &'a T)>
+	where
+		K: Clone,
+	{
+		unsafe {
+			Iter::new(RwLockReadGuard::map(
+				self.contents.read(/* poisoned */),
+				|contents| &contents.addresses,
+			))
+		}
+		.filter_map(move |(key, value)| f(value).map(|projected| (key, projected)))
+	}
+
+	/// Returns a handle to the value corresponding to the key, which doesn't borrow the instance.
+	///
+	/// The handle can be resolved again through [`PinnedHandle::get`] for as long as no entries may have been removed,
+	/// i.e. it survives insertions through shared references but not [`.clear()`](`UnpinnedPineMap::clear`) or removals.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn get_handle<Q>(&self, key: &Q) -> Option<PinnedHandle<V>>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents.addresses.get(key).map(|value| PinnedHandle {
+			value: *value,
+			generation: contents.generation,
+		})
+	}
+
+	/// Returns pointers to all values, in key order, as captured under a single brief lock acquisition.
+	///
+	/// The pointers stay valid for reads until the respective entry is removed or the instance is cleared or dropped,
+	/// which requires exclusive access. They must not be written through while the instance is shared.
+	pub fn value_ptrs(&self) -> Vec<NonNull<V>> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.map(|value| unsafe { NonNull::new_unchecked(*value) })
+			.collect()
+	}
+
+	/// Returns how many bytes of the backing arena are in use, including for holes and padding.
+	pub fn allocated_bytes(&self) -> usize {
+		self.contents.read(/* poisoned */).memory.allocated_bytes()
+	}
+
+	/// Estimates the instance's total heap footprint in bytes:
+	/// the [`allocated_bytes`](`PressedPineMap::allocated_bytes`) and the key index.
+	///
+	/// The index's node layout isn't public, so its share is only a rough estimate
+	/// that assumes nodes are about two thirds full.
+	/// Values that a factory placed outside the arena aren't counted.
+	pub fn total_memory_estimate(&self) -> usize {
+		let contents = self.contents.read(/* poisoned */);
+		contents.memory.allocated_bytes() + estimate_index_bytes::<K, V>(contents.addresses.len())
+	}
+
+	/// Moves a boxed value into the instance, but only if no such key exists yet.
+	///
+	/// Unlike [`.emplace(…)`](`UnpinnedPineMapEmplace::emplace`), this also works with unsized values.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	pub fn emplace_boxed(&self, key: K, value: Box<V>) -> Fine<&V, (K, Box<V>)> {
+		let mut contents = self.write_counted();
+		let PressedCambium {
+			addresses,
+			memory,
+			values_need_drop,
+			drop_phase: _,
+			generation: _,
+		} = &mut *contents;
+		#[allow(clippy::map_entry)]
+		if let Some(existing_value) = addresses.get(&key) {
+			(unsafe { &**existing_value }, Err((key, value)))
+		} else {
+			let value = move_out_of_box(value, memory);
+			addresses.insert(key, value);
+			*values_need_drop = true;
+			(unsafe { &*value }, Ok(()))
+		}
+	}
+
+	/// Removes a matching entry and moves its value into a new [`Box`].
+	///
+	/// Unlike [`.remove_pair(…)`](`UnpinnedPineMap::remove_pair`), this also works with unsized values.
+	pub fn remove_boxed<Q>(&mut self, key: &Q) -> Option<(K, Box<V>)>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let mut contents = self.get_mut_counted();
+		let (key, value) = contents.addresses.remove_entry(key)?;
+		Some((key, unsafe { move_into_box(value) }))
+	}
+
+	/// Removes and returns the value corresponding to the key, reclaiming its arena space where possible.
+	///
+	/// **This may move another value:** If the most recently allocated value isn't the removed one,
+	/// it's moved into the freed slot so that the arena's end can be rewound for the next allocation.
+	/// That's why this is only available on unpinned instances.
+	///
+	/// Finding the value to move takes linear time in the number of entries.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+	where
+		V: Sized,
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let mut contents = self.get_mut_counted();
+		let PressedCambium {
+			addresses, memory, ..
+		} = &mut *contents;
+		let removed = addresses.remove(key)?;
+		let value = unsafe { removed.read() };
+
+		// The current chunk's allocated part starts with the last allocation.
+		let last = match unsafe { memory.iter_allocated_chunks_raw() }.next() {
+			Some((last, len)) if len > 0 && mem::size_of::<V>() > 0 => last.cast::<V>(),
+			_ => return Some(value),
+		};
+		if removed != last {
+			match addresses.values_mut().find(|value| **value == last) {
+				Some(moved) => {
+					// The removed value held a `V` there, so the slot is valid and aligned for one.
+					unsafe { last.copy_to_nonoverlapping(removed, 1) };
+					*moved = removed;
+				}
+				None => return Some(value),
+			}
+		}
+		// Freeing the last allocation rewinds the arena.
+		// (`Bump`'s deallocation isn't public, but a `Vec`'s is.)
+		drop(unsafe { bumpalo::collections::Vec::from_raw_parts_in(last, 0, 1, memory) });
+		Some(value)
+	}
+
+	/// Returns the number of entries without locking.
+	///
+	/// This reads a counter with [`Relaxed`](`atomic::Ordering::Relaxed`) ordering,
+	/// so it's only exact while no other thread is inserting (or once such insertions are otherwise synchronised with).
+	/// Use [`.len()`](`UnpinnedPineMap::len`) for an exact count.
+	pub fn len_relaxed(&self) -> usize {
+		self.len.load(atomic::Ordering::Relaxed)
+	}
+
+	fn write_counted(&self) -> Counted<'_, RwLockWriteGuard<'_, PressedCambium<K, V>>> {
+		Counted {
+			contents: self.contents.write(/* poisoned */),
+			len: &self.len,
+		}
+	}
+
+	fn get_mut_counted(&mut self) -> Counted<'_, &mut PressedCambium<K, V>> {
+		let contents = self.contents.get_mut(/* poisoned */);
+		// Conservatively invalidates handles, as this is how entries are removed.
+		contents.generation = next_generation();
+		Counted {
+			contents,
+			len: &self.len,
+		}
+	}
+}
+
+impl<K: Ord, T> PressedPineMap<K, [T]> {
+	/// Stores clones of `items` as new slice value, but only if no such key exists yet.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists, in which case `items` are cloned into a [`Vec`] instead.
+	pub fn push_slice(&self, key: K, items: &[T]) -> Fine<&[T], (K, Vec<T>)>
+	where
+		T: Clone,
+	{
+		let mut contents = self.write_counted();
+		let PressedCambium {
+			addresses,
+			memory,
+			values_need_drop,
+			drop_phase: _,
+			generation: _,
+		} = &mut *contents;
+		#[allow(clippy::map_entry)]
+		if let Some(existing_value) = addresses.get(&key) {
+			(unsafe { &**existing_value }, Err((key, items.to_vec())))
+		} else {
+			let entry = vacant_entry(addresses, key);
+			let value = memory.alloc_slice_clone(items);
+			entry.insert(value as *mut _);
+			*values_need_drop |= mem::needs_drop::<T>();
+			(unsafe { &*(value as *const _) }, Ok(()))
+		}
+	}
+}
+
+impl<K: Ord> PressedPineMap<K, dyn Any> {
+	/// Iterates over the entries whose value is a `T`, in key order.
+	///
+	/// The keys are cloned because they may move as soon as the instance is unlocked again.
+	///
+	/// # Panics
+	///
+	/// Iff the instance was poisoned.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread meanwhile deadlocks.
+	pub fn iter_downcast<T: Any>(&self) -> IterDowncast<'_, K, T>
+	where
+		K: Clone,
+	{
+		IterDowncast {
+			entries: unsafe {
+				Iter::new(RwLockReadGuard::map(
+					self.contents.read(/* poisoned */),
+					|contents| &contents.addresses,
+				))
+			},
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<K: Ord, V> Cambium<K, V> {
+	fn is_full(&self) -> bool {
+		self.addresses.len() >= self.max_entries
+	}
+
+	/// Allocates as many slots at once as needed to have at least `count` holes.
+	fn reserve_holes(&mut self, count: usize) {
+		let missing = count.saturating_sub(self.holes.len());
+		if missing > 0 {
+			let chunks = chunk_count(&self.memory, self.growth_observer.as_deref());
+			let slots = self
+				.memory
+				.alloc_slice_fill_with(missing, |_| MaybeUninit::uninit());
+			notify_growth(&self.memory, chunks, self.growth_observer.as_deref());
+			// Reversed, so that the slots are popped off in order.
+			self.holes
+				.extend(slots.iter_mut().rev().map(|slot| slot as *mut _));
+		}
+	}
+
+	/// Releases the holes that make up the most recent allocations in the arena's current chunk,
+	/// which rewinds it past them.
+	fn rewind_holes(&mut self) {
+		let size = mem::size_of::<V>();
+		let (top, len) = match unsafe { self.memory.iter_allocated_chunks_raw() }.next() {
+			// The current chunk's allocated part starts with the last allocation.
+			Some((top, len)) if size > 0 => (top.cast::<MaybeUninit<V>>(), len),
+			_ => return,
+		};
+		let holes = self
+			.holes
+			.iter()
+			.map(|&hole| hole as usize)
+			.collect::<BTreeSet<_>>();
+		let count = (0..len / size)
+			.take_while(|i| holes.contains(&(top as usize + i * size)))
+			.count();
+		if count > 0 {
+			let freed = top as usize..top as usize + count * size;
+			self.holes.retain(|&hole| !freed.contains(&(hole as usize)));
+			// Freeing the last allocation rewinds the arena.
+			// (`Bump`'s deallocation isn't public, but a `Vec`'s is.)
+			drop(unsafe {
+				bumpalo::collections::Vec::from_raw_parts_in(top, 0, count, &self.memory)
+			});
+		}
+	}
+
+	fn try_emplace_with<
+		F: for<'a> FnOnce(&K, &'a mut MaybeUninit<V>) -> Result<&'a mut V, E>,
+		E,
+	>(
+		&mut self,
+		key: K,
+		value_factory: F,
+	) -> Result<Fine<*mut V, (K, F)>, E> {
+		assert!(
+			!self.is_full() || self.addresses.contains_key(&key),
+			"Tried to add an entry beyond the `PineMap`'s `max_entries`."
+		);
+		let Cambium {
+			addresses,
+			memory,
+			holes,
+			max_entries: _,
+			drop_phase: _,
+			growth_observer,
+		} = self;
+		#[allow(clippy::map_entry)]
+		if let Some(&existing_value) = addresses.get(&key) {
+			// Only a pointer, as shared references to the existing value may be live.
+			(existing_value, Err((key, value_factory)))
+		} else {
+			// Holding on to the vacant entry statically rules out changes to `addresses` during the factory call.
+			let entry = vacant_entry(addresses, key);
+			let slot = holes.pop().unwrap_or_else(|| {
+				let chunks = chunk_count(memory, growth_observer.as_deref());
+				let slot = memory.alloc(MaybeUninit::uninit());
+				notify_growth(memory, chunks, growth_observer.as_deref());
+				slot
+			});
+			// A slot the factory didn't use is kept around as hole, whether it was one before or not,
+			// also if the factory panics. The vacant entry is then simply dropped along with the key.
+			let value = catch_unwind(AssertUnwindSafe(|| {
+				value_factory(entry.key(), unsafe { &mut *slot })
+			}))
+			.unwrap_or_else(|panic| {
+				holes.push(slot);
+				panic::resume_unwind(panic)
+			})
+			.tap_err(|_| holes.push(slot))?;
+			let value: *mut V = value;
+			entry.insert(value);
+			(value, Ok(()))
+		}
+		.pipe(Ok)
+	}
+}
+
+/// Locked contents that republish their entry count when released,
+/// so that it can be read without taking the lock.
+struct Counted<'a, G: DerefMut>
+where
+	G::Target: EntryCount,
+{
+	contents: G,
+	len: &'a AtomicUsize,
+}
+
+trait EntryCount {
+	fn entry_count(&self) -> usize;
+}
+
+impl<K, V> EntryCount for Cambium<K, V> {
+	fn entry_count(&self) -> usize {
+		self.addresses.len()
+	}
+}
+
+impl<K, V: ?Sized> EntryCount for PressedCambium<K, V> {
+	fn entry_count(&self) -> usize {
+		self.addresses.len()
+	}
+}
+
+impl<G: DerefMut> Deref for Counted<'_, G>
+where
+	G::Target: EntryCount,
+{
+	type Target = G::Target;
+
+	fn deref(&self) -> &Self::Target {
+		&self.contents
+	}
+}
+
+impl<G: DerefMut> DerefMut for Counted<'_, G>
+where
+	G::Target: EntryCount,
+{
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.contents
+	}
+}
+
+impl<G: DerefMut> Drop for Counted<'_, G>
+where
+	G::Target: EntryCount,
+{
+	fn drop(&mut self) {
+		// Still locked here, as the fields are dropped only afterwards.
+		self.len
+			.store(self.contents.entry_count(), atomic::Ordering::Relaxed);
+	}
+}
+
+/// Returned by [`PineMap::insert_bounded`] and [`PineMap::try_emplace_with_bounded`]
+/// when a new entry would exceed the instance's `max_entries`.
+#[derive(Debug)]
+pub struct CapacityExceeded<K, V> {
+	/// The key that wasn't inserted.
+	pub key: K,
+	/// The value (or value factory) that wasn't inserted.
+	pub value: V,
+}
+
+/// A handle to a [`PressedPineMap`]'s value that doesn't borrow the instance, obtained through [`PressedPineMap::get_handle`].
+///
+/// It's invalidated by any [`.clear()`](`UnpinnedPineMap::clear`) or removal (and conservatively, by most other methods taking `&mut self`),
+/// even though a [`PressedPineMap`] doesn't reuse value memory before it's cleared.
+pub struct PinnedHandle<V: ?Sized> {
+	value: *const V,
+	generation: u64,
+}
+
+impl<V: ?Sized> PinnedHandle<V> {
+	/// Returns a reference to the value, iff `map` is the instance this handle was obtained from and the handle is still valid.
+	pub fn get<'a, K: Ord>(&self, map: &'a PressedPineMap<K, V>) -> Option<&'a V> {
+		(map.contents.read(/* poisoned */).generation == self.generation)
+			.then(|| unsafe { &*self.value })
+	}
+
+	/// Returns a reference to the pinned value, iff `map` is the instance this handle was obtained from and the handle is still valid.
+	pub fn get_pinned<'a, K: Ord>(&self, map: &'a Pin<PressedPineMap<K, V>>) -> Option<Pin<&'a V>> {
+		self.get(map.as_unpinned())
+			.map(|value| unsafe { Pin::new_unchecked(value) })
+	}
+}
+
+impl<V: ?Sized> Clone for PinnedHandle<V> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<V: ?Sized> Copy for PinnedHandle<V> {}
+
+impl<V: ?Sized> Debug for PinnedHandle<V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PinnedHandle")
+			.field("generation", &self.generation)
+			.finish_non_exhaustive()
+	}
+}
+
+/// Returns a generation that no instance has used before.
+fn next_generation() -> u64 {
+	static NEXT: AtomicU64 = AtomicU64::new(0);
+	NEXT.fetch_add(1, atomic::Ordering::Relaxed)
+}
+
+/// Returned by [`PineMap::try_with_capacity`] when memory can't be reserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryReserveError {
+	/// The required size in bytes overflows.
+	CapacityOverflow,
+	/// The memory allocator returned an error.
+	AllocError,
+}
+
+impl fmt::Display for TryReserveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			TryReserveError::CapacityOverflow => {
+				"The requested capacity exceeds the maximum allocation size."
+			}
+			TryReserveError::AllocError => "The memory allocator returned an error.",
+		})
+	}
+}
+
+impl std::error::Error for TryReserveError {}
+
+/// The order in which a map drops its keys and values when it's [cleared](`UnpinnedPineMap::clear`) or dropped,
+/// and when draining or extracting iterators drop the remaining removed entries.
+///
+/// Entries are always visited in key order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DropPhase {
+	/// Each entry's key is dropped right before its value. This is the default.
+	Interleaved,
+	/// All values are dropped before any key.
+	ValuesThenKeys,
+	/// All keys are dropped before any value.
+	KeysThenValues,
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` isn't available at the MSRV.
+impl Default for DropPhase {
+	fn default() -> Self {
+		DropPhase::Interleaved
+	}
+}
+
+/// Shared access to a [`PineMap`]'s contents that can be upgraded to exclusive access,
+/// obtained through [`PineMap::upgradable_read`].
+pub struct UpgradableReadGuard<'a, K: Ord, V> {
+	contents: RwLockUpgradableReadGuard<'a, Cambium<K, V>>,
+	len: &'a AtomicUsize,
+}
+
+impl<'a, K: Ord, V> UpgradableReadGuard<'a, K, V> {
+	/// Returns a reference to the value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents
+			.addresses
+			.get(key)
+			.map(|value| unsafe { &**value })
+	}
+
+	/// Checks whether an entry matching `key` exists.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn contains_key<Q>(&self, key: &Q) -> bool
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents.addresses.contains_key(key)
+	}
+
+	/// Atomically upgrades this guard into a [`WriteGuard`].
+	///
+	/// This waits for other readers to finish, but no writer can get in between.
+	#[must_use]
+	pub fn upgrade(self) -> WriteGuard<'a, K, V> {
+		WriteGuard {
+			contents: Counted {
+				contents: RwLockUpgradableReadGuard::upgrade(self.contents),
+				len: self.len,
+			},
+		}
+	}
+}
+
+/// Exclusive access to a [`PineMap`]'s contents, obtained through [`PineMap::write`] or [`UpgradableReadGuard::upgrade`].
+///
+/// Value references handed out by this guard remain valid for the lifetime of the map borrow (`'a`),
+/// as values can't be moved or removed through a shared reference.
+pub struct WriteGuard<'a, K: Ord, V> {
+	contents: Counted<'a, RwLockWriteGuard<'a, Cambium<K, V>>>,
+}
+
+#[allow(clippy::missing_panics_doc)] // Only unreachable panics.
+impl<'a, K: Ord, V> WriteGuard<'a, K, V> {
+	/// Returns a reference to the value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents
+			.addresses
+			.get(key)
+			.map(|value| unsafe { &**value })
+	}
+
+	/// Checks whether an entry matching `key` exists.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn contains_key<Q>(&self, key: &Q) -> bool
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents.addresses.contains_key(key)
+	}
+
+	/// Tries to insert a new value produced by the given factory, but only if no such key exists yet.
+	///
+	/// # Errors
+	///
+	/// Outer error: Iff `value_factory` fails.
+	///
+	/// Inner error: Iff an entry matching `key` already exists.
+	pub fn try_insert_with<F: FnOnce(&K) -> Result<V, E>, E>(
+		&mut self,
+		key: K,
+		value_factory: F,
+	) -> Result<Fine<&'a V, (K, F)>, E> {
+		let value_factory = Cell::new(Some(value_factory));
+		self.contents
+			.try_emplace_with(key, |key, slot| {
+				slot.write(value_factory.take().expect("unreachable")(key)?)
+					.pipe(Ok)
+			})?
+			.map(|value| unsafe { &*value })
+			.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
+			.pipe(Ok)
+	}
+
+	/// Inserts a new value, but only if no such key exists yet.
+	///
+	/// # Errors
+	///
+	/// Iff an entry matching `key` already exists.
+	pub fn insert(&mut self, key: K, value: V) -> Fine<&'a V, (K, V)> {
+		let value = Cell::new(Some(value));
+		self.try_insert_with(key, |_| {
+			Ok::<_, Infallible>(value.take().expect("unreachable"))
+		})
+		.unwrap_infallible()
+		.map_err(|(key, _)| (key, value.take().expect("unreachable")))
+	}
+
+	/// Returns a reference to the value corresponding to the key,
+	/// inserting a new value produced by the given factory first if no such key exists yet.
+	pub fn get_or_insert_with<F: FnOnce(&K) -> V>(&mut self, key: K, value_factory: F) -> &'a V {
+		self.try_insert_with(key, |key| Ok::<_, Infallible>(value_factory(key)))
+			.unwrap_infallible()
+			.0
+	}
+}
+
+/// Iterates over the entries of a [`PressedPineMap<K, dyn Any>`] whose value is a `T`,
+/// obtained through [`PressedPineMap::iter_downcast`].
+///
+/// This holds the instance's read lock until dropped, so it can be shared but not sent between threads.
+pub struct IterDowncast<'a, K: Ord, T> {
+	entries: Iter<'a, K, dyn Any>,
+	_phantom: PhantomData<&'a T>,
+}
+
+impl<'a, K: Ord + Clone, T: Any> Iterator for IterDowncast<'a, K, T> {
+	type Item = (K, &'a T);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.entries
+			.by_ref()
+			.find_map(|(key, value)| value.downcast_ref().map(|value| (key, value)))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(0, self.entries.size_hint().1)
+	}
+}
+
+/// Yields a [`PressedPineMap`]'s entries in key order, moving each value into a new [`Box`].
+///
+/// Entries that haven't been yielded yet are dropped when this iterator is dropped,
+/// even if some of them panic while being done so (as with [`.clear()`](`UnpinnedPineMap::clear`)).
+pub struct IntoIter<K, V: ?Sized> {
+	// Declared first, so that the remaining values are dropped before their arena.
+	entries: btree_map::IntoIter<K, *mut V>,
+	_memory: Bump,
+	values_need_drop: bool,
+	drop_phase: DropPhase,
+}
+
+impl<K, V: ?Sized> Iterator for IntoIter<K, V> {
+	type Item = (K, Box<V>);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.next()?;
+		Some((key, unsafe { move_into_box(value) }))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.entries.size_hint()
+	}
+}
+
+impl<K, V: ?Sized> DoubleEndedIterator for IntoIter<K, V> {
+	fn next_back(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.next_back()?;
+		Some((key, unsafe { move_into_box(value) }))
+	}
+}
+
+impl<K, V: ?Sized> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V: ?Sized> Drop for IntoIter<K, V> {
+	fn drop(&mut self) {
+		if self.values_need_drop {
+			drop_all_pinned(self.entries.by_ref(), self.drop_phase);
+		}
+	}
+}
+
+impl<K: Ord, V: ?Sized> IntoIterator for PressedPineMap<K, V> {
+	type Item = (K, Box<V>);
+	type IntoIter = IntoIter<K, V>;
+
+	fn into_iter(mut self) -> Self::IntoIter {
+		let mut contents = self.get_mut_counted();
+		IntoIter {
+			entries: mem::take(&mut contents.addresses).into_iter(),
+			_memory: mem::replace(&mut contents.memory, Bump::new()),
+			// The now empty instance then doesn't drop anything.
+			values_need_drop: mem::replace(&mut contents.values_need_drop, false),
+			drop_phase: contents.drop_phase,
+		}
+	}
+}
+
+/// Removes and yields a range of a [`PineMap`]'s entries, obtained through [`PineMap::drain_range`].
+///
+/// Entries that haven't been yielded yet are removed and dropped when this iterator is dropped.
+pub struct DrainRange<'a, K: Ord, V> {
+	contents: Counted<'a, &'a mut Cambium<K, V>>,
+	entries: btree_map::IntoIter<K, *mut V>,
+}
+
+impl<K: Ord, V> Iterator for DrainRange<'_, K, V> {
+	type Item = (K, V);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.entries.next()?;
+		// The value must be moved out before its slot becomes available for reuse.
+		let value_read = unsafe { value.read() };
+		self.contents.holes.push(value.cast());
+		Some((key, value_read))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.entries.size_hint()
+	}
+}
+
+impl<K: Ord, V> Drop for DrainRange<'_, K, V> {
+	fn drop(&mut self) {
+		let Cambium {
+			holes, drop_phase, ..
+		} = &mut *self.contents;
+		drop_all_pinned(
+			self.entries
+				.by_ref()
+				.inspect(|(_, value)| holes.push(value.cast())),
+			*drop_phase,
+		);
+	}
+}
+
+/// A [`PineMap`] that can only grow, so that value references stay valid for as long as it's borrowed.
+///
+/// Unlike a shared borrow of a [`PineMap`], which only keeps it from being modified until the borrow ends,
+/// this owns its map and doesn't expose any way to remove entries short of [`.into_inner()`](`AppendOnly::into_inner`).
+///
+/// # Example
+///
+/// ```rust
+/// use pinus::sync::AppendOnly;
+///
+/// let strings = AppendOnly::new();
+/// let a: &String = strings.get_or_insert("a", "A".to_string());
+/// let b: &String = strings.get_or_insert("b", "B".to_string());
+/// assert!(std::ptr::eq(a, strings.get_or_insert("a", "ignored".to_string())));
+/// assert_eq!((a.as_str(), b.as_str()), ("A", "B"));
+/// ```
+pub struct AppendOnly<K: Ord, V> {
+	map: PineMap<K, V>,
+}
+
+impl<K: Ord, V> AppendOnly<K, V> {
+	/// Creates a new empty [`AppendOnly`].
+	#[must_use]
+	pub fn new() -> Self {
+		Self {
+			map: PineMap::new(),
+		}
+	}
+
+	/// Unwraps the underlying [`PineMap`], which allows removals again.
+	#[must_use]
+	pub fn into_inner(self) -> PineMap<K, V> {
+		self.map
+	}
+
+	/// Returns a reference to the value corresponding to the key.
+	///
+	/// The key may be any borrowed form of the map's key type,
+	/// but the ordering on the borrowed form *must* match the ordering on the key type.
+	pub fn get<Q>(&self, key: &Q) -> Option<&V>
+	where
+		K: Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.map.get(key)
+	}
+
+	/// Returns a reference to the value corresponding to the key, inserting `value` first if no such key exists yet.
+	///
+	/// `value` is dropped if the key already exists.
+	pub fn get_or_insert(&self, key: K, value: V) -> &V {
+		self.map.insert(key, value).0
+	}
+
+	/// Returns a reference to the value corresponding to the key,
+	/// inserting a new value produced by the given factory first if no such key exists yet.
+	pub fn get_or_insert_with<F: FnOnce(&K) -> V>(&self, key: K, value_factory: F) -> &V {
+		self.map.insert_with(key, value_factory).0
+	}
+
+	/// Returns the number of entries.
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	/// Checks whether there are no entries.
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	/// Iterates over clones of all keys along with references to their values, in key order.
+	///
+	/// The instance stays read-locked until the iterator is dropped,
+	/// so inserting into it on the same thread in the meantime deadlocks.
+	pub fn iter(&self) -> Iter<'_, K, V>
+	where
+		K: Clone,
+	{
+		unsafe {
+			Iter::new(RwLockReadGuard::map(
+				self.map.contents.read(/* poisoned */),
+				|contents| &contents.addresses,
+			))
+		}
+	}
+}
+
+impl<K: Ord, V> Default for AppendOnly<K, V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a, K: Ord + Clone, V> IntoIterator for &'a AppendOnly<K, V> {
+	type Item = (K, &'a V);
+	type IntoIter = Iter<'a, K, V>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+impl<K: Ord, V> From<PineMap<K, V>> for AppendOnly<K, V> {
+	fn from(map: PineMap<K, V>) -> Self {
+		Self { map }
+	}
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for AppendOnly<K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("AppendOnly").field(&self.map).finish()
+	}
+}
+
+/// Removes a [`PineMap`]'s entries whose key matches a predicate and yields those keys,
+/// obtained through [`PineMap::extract_keys_if`] or [`PineMap::pinned_extract_keys_if`].
+pub struct ExtractKeysIf<'a, K: Ord, V, F: FnMut(&K) -> bool> {
+	contents: Counted<'a, &'a mut Cambium<K, V>>,
+	/// The first key that hasn't been checked yet.
+	///
+	/// This points into `contents.addresses`, which is only modified while advancing.
+	next_key: Option<*const K>,
+	pred: F,
+}
+
+impl<K: Ord, V, F: FnMut(&K) -> bool> ExtractKeysIf<'_, K, V, F> {
+	/// Removes the next matching entry and makes its slot available for reuse.
+	///
+	/// Unvisited entries stay in the map throughout, so leaking the iterator can't lose them.
+	fn extract_next(&mut self) -> Option<(K, *mut V)> {
+		let next_key = unsafe { &*self.next_key? };
+		let Cambium {
+			addresses, holes, ..
+		} = &mut *self.contents;
+
+		let pred = &mut self.pred;
+		let found = addresses
+			.range(next_key..)
+			.map(|(key, _)| key as *const K)
+			.find(|&key| pred(unsafe { &*key }));
+		if found.is_none() {
+			self.next_key = None;
+		}
+		let found = found?;
+
+		// `found` points into the map that's about to be modified, so a bitwise copy serves as lookup key instead.
+		// The copy is only compared, never dropped.
+		let lookup = ManuallyDrop::new(unsafe { found.read() });
+		let (key, value) = addresses
+			.remove_entry(&*lookup)
+			.expect("inconsistent `Ord` implementation");
+		self.next_key = addresses
+			.range((Bound::Excluded(&key), Bound::Unbounded))
+			.next()
+			.map(|(key, _)| key as *const K);
+
+		// Exclusive access rules out reuse until the value is dropped.
+		holes.push(value.cast());
+		Some((key, value))
+	}
+}
+
+impl<K: Ord, V, F: FnMut(&K) -> bool> Iterator for ExtractKeysIf<'_, K, V, F> {
+	type Item = K;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.extract_next()?;
+		unsafe { value.drop_in_place() };
+		Some(key)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		match self.next_key {
+			Some(_) => (0, Some(self.contents.addresses.len())),
+			None => (0, Some(0)),
+		}
+	}
+}
+
+impl<K: Ord, V, F: FnMut(&K) -> bool> Drop for ExtractKeysIf<'_, K, V, F> {
+	fn drop(&mut self) {
+		if thread::panicking() {
+			return;
+		}
+
+		// Entries removed before `pred` panics are still dropped.
+		let mut removed = vec![];
+		let extracted = catch_unwind(AssertUnwindSafe(|| {
+			while let Some(entry) = self.extract_next() {
+				removed.push(entry);
+			}
+		}));
+		drop_all_pinned(removed, self.contents.drop_phase);
+		if let Err(panic) = extracted {
+			panic::resume_unwind(panic)
+		}
+	}
+}
+
+/// A view into a single entry of a [`PineMap`], obtained through [`PineMap::entry`].
+pub enum Entry<'a, K: Ord, V> {
+	/// The key was present.
+	Occupied(OccupiedEntry<'a, K, V>),
+	/// The key was absent.
+	Vacant(VacantEntry<'a, K, V>),
+}
+
+/// A view into an existing entry of a [`PineMap`].
+pub struct OccupiedEntry<'a, K: Ord, V> {
+	key: K,
+	value: &'a V,
+}
+
+/// A view into a missing entry of a [`PineMap`], which holds the write lock until consumed or dropped.
+pub struct VacantEntry<'a, K: Ord, V> {
+	contents: Counted<'a, RwLockWriteGuard<'a, Cambium<K, V>>>,
+	key: K,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+	/// Returns the key that was used to look up this entry.
+	pub fn key(&self) -> &K {
+		match self {
+			Entry::Occupied(entry) => entry.key(),
+			Entry::Vacant(entry) => entry.key(),
+		}
+	}
+
+	/// Returns a reference to the existing value or inserts `value`.
+	pub fn or_insert(self, value: V) -> &'a V {
+		self.or_insert_with(|| value)
+	}
+
+	/// Returns a reference to the existing value or inserts one produced by `value_factory`.
+	pub fn or_insert_with<F: FnOnce() -> V>(self, value_factory: F) -> &'a V {
+		match self {
+			Entry::Occupied(entry) => entry.get(),
+			Entry::Vacant(entry) => entry.insert(value_factory()),
+		}
+	}
+
+	/// Returns a reference to the existing value or inserts one produced by `value_factory` from the key.
+	///
+	/// `value_factory` only runs if the entry is vacant.
+	pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, value_factory: F) -> &'a V {
+		self.or_try_insert_with(|key| Ok::<_, Infallible>(value_factory(key)))
+			.unwrap_or_else(|(_, infallible)| match infallible {})
+	}
+
+	/// Returns a reference to the existing value or tries to insert one produced by `value_factory`.
+	///
+	/// # Errors
+	///
+	/// Iff `value_factory` fails, in which case the (still locked) [`VacantEntry`] is returned alongside the error.
+	pub fn or_try_insert_with<F: FnOnce(&K) -> Result<V, E>, E>(
+		self,
+		value_factory: F,
+	) -> Result<&'a V, (VacantEntry<'a, K, V>, E)> {
+		match self {
+			Entry::Occupied(entry) => Ok(entry.get()),
+			Entry::Vacant(entry) => entry.try_insert_with(value_factory),
+		}
+	}
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+	/// Returns the key that was used to look up this entry.
+	///
+	/// This is not the key instance stored in the map.
+	pub fn key(&self) -> &K {
+		&self.key
+	}
+
+	/// Returns the key that was used to look up this entry.
+	pub fn into_key(self) -> K {
+		self.key
+	}
+
+	/// Returns a reference to the existing value.
+	pub fn get(&self) -> &'a V {
+		self.value
+	}
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+	/// Returns the key that will be inserted.
+	pub fn key(&self) -> &K {
+		&self.key
+	}
+
+	/// Releases the write lock and returns the key.
+	pub fn into_key(self) -> K {
+		self.key
+	}
+
+	/// Inserts `value` and returns a reference to it.
+	pub fn insert(self, value: V) -> &'a V {
+		match self.try_insert_with(|_| Ok::<_, Infallible>(value)) {
+			Ok(value) => value,
+			Err((_, infallible)) => match infallible {},
+		}
+	}
+
+	/// Tries to insert a value produced by `value_factory` and returns a reference to it.
+	///
+	/// # Errors
+	///
+	/// Iff `value_factory` fails, in which case this entry is returned alongside the error.
+	/// It's still locked, so no other thread can have inserted the key in the meantime.
+	pub fn try_insert_with<F: FnOnce(&K) -> Result<V, E>, E>(
+		self,
+		value_factory: F,
+	) -> Result<&'a V, (Self, E)> {
+		let value = match value_factory(&self.key) {
+			Ok(value) => value,
+			Err(error) => return Err((self, error)),
+		};
+		let VacantEntry { mut contents, key } = self;
+		let (value, _) = contents
+			.try_emplace_with(key, |_, slot| Ok::<_, Infallible>(slot.write(value)))
+			.unwrap_infallible();
+		Ok(unsafe { &*value })
+	}
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for Entry<'_, K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Entry::Occupied(entry) => f.debug_tuple("Entry").field(entry).finish(),
+			Entry::Vacant(entry) => f.debug_tuple("Entry").field(entry).finish(),
+		}
+	}
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for OccupiedEntry<'_, K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("OccupiedEntry")
+			.field("key", &self.key)
+			.field("value", &self.value)
+			.finish()
+	}
+}
+
+impl<K: Ord + Debug, V> Debug for VacantEntry<'_, K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("VacantEntry").field(&self.key).finish()
+	}
+}
+
+/// A view into a single entry of a [`PineMap`], obtained through [`PineMap::entry_mut`].
+///
+/// As this borrows the instance exclusively, it gives out mutable access to the value.
+pub struct EntryMut<'a, K: Ord, V> {
+	contents: Counted<'a, &'a mut Cambium<K, V>>,
+	key: K,
+}
+
+impl<'a, K: Ord, V> EntryMut<'a, K, V> {
+	/// Returns the key that was used to look up this entry.
+	///
+	/// This is not the key instance stored in the map.
+	pub fn key(&self) -> &K {
+		&self.key
+	}
+
+	/// Runs `f` on the existing value, if any.
+	#[must_use]
+	pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+		if let Some(&value) = self.contents.addresses.get(&self.key) {
+			f(unsafe { &mut *value });
+		}
+		self
+	}
+
+	/// Returns a mutable reference to the existing value or inserts `value`.
+	pub fn or_insert(self, value: V) -> &'a mut V {
+		self.or_insert_with(|| value)
+	}
+
+	/// Returns a mutable reference to the existing value or inserts one produced by `value_factory`.
+	pub fn or_insert_with<F: FnOnce() -> V>(self, value_factory: F) -> &'a mut V {
+		let EntryMut { mut contents, key } = self;
+		let (value, _) = contents
+			.try_emplace_with(key, |_, slot| {
+				Ok::<_, Infallible>(slot.write(value_factory()))
+			})
+			.unwrap_infallible();
+		unsafe { &mut *value }
+	}
+}
+
+impl<K: Ord + Debug, V> Debug for EntryMut<'_, K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("EntryMut").field(&self.key).finish()
+	}
+}
+
+/// A view into a single entry of a pinned [`PineMap`], obtained through [`PineMap::pinned_entry`].
+///
+/// As this borrows the instance exclusively, it gives out mutable (but pinned) access to the value.
+pub struct PinnedEntry<'a, K: Ord, V> {
+	contents: Counted<'a, &'a mut Cambium<K, V>>,
+	key: K,
+}
+
+impl<'a, K: Ord, V> PinnedEntry<'a, K, V> {
+	/// Returns the key that was used to look up this entry.
+	///
+	/// This is not the key instance stored in the map.
+	pub fn key(&self) -> &K {
+		&self.key
+	}
+
+	/// Returns a mutable reference to the existing pinned value or inserts `value`.
+	pub fn or_insert(self, value: V) -> Pin<&'a mut V> {
+		self.or_insert_with(|| value)
+	}
+
+	/// Returns a mutable reference to the existing pinned value or inserts one produced by `value_factory`.
+	pub fn or_insert_with<F: FnOnce() -> V>(self, value_factory: F) -> Pin<&'a mut V> {
+		let PinnedEntry { mut contents, key } = self;
+		let (value, _) = contents
+			.try_emplace_with(key, |_, slot| {
+				Ok::<_, Infallible>(slot.write(value_factory()))
+			})
+			.unwrap_infallible();
+		unsafe { Pin::new_unchecked(&mut *value) }
+	}
+}
+
+impl<K: Ord + Debug, V> Debug for PinnedEntry<'_, K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("PinnedEntry").field(&self.key).finish()
+	}
+}
+
+impl<K: Ord, V> Default for PineMap<K, V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Clones into a [compacted](`PineMap::compact_into`) instance.
+impl<K: Ord + Clone, V: Clone> Clone for PineMap<K, V> {
+	fn clone(&self) -> Self {
+		self.compact_into()
+	}
+
+	/// Clears `self` (keeping its arena's capacity) and then emplaces clones of `source`'s entries.
+	///
+	/// Repeatedly refreshing the same destination this way doesn't reallocate once the arena is large enough.
+	fn clone_from(&mut self, source: &Self) {
+		self.clear();
+
+		let source = source.contents.read(/* poisoned */);
+		let mut target = self.get_mut_counted();
+		// `source` respects its own limit, so copying its entries can't run into it.
+		target.max_entries = source.max_entries;
+		target.drop_phase = source.drop_phase;
+		for (key, value) in &source.addresses {
+			let value = unsafe { &**value };
+			let _ = target.try_emplace_with(key.clone(), |_, slot| {
+				Ok::<_, Infallible>(slot.write(value.clone()))
+			});
+		}
+	}
+}
+
+impl<K: Ord, V: ?Sized> Default for PressedPineMap<K, V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Formats like a [`BTreeMap`].
+///
+/// The alternate form (`{:#?}`) additionally includes a `memory` section with
+/// the number of live entries, the [`hole_count`](`PineMap::hole_count`) and
+/// the [`allocated_bytes`](`PineMap::allocated_bytes`).
+#[allow(clippy::missing_fields_in_debug)] // The fields are formatted through the lock.
+impl<K: Ord + Debug, V: Debug> Debug for PineMap<K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let contents = self.contents.read(/* poisoned */);
+		let entries = DebugEntries(&contents.addresses);
+		if f.alternate() {
+			f.debug_struct("PineMap")
+				.field("entries", &entries)
+				.field(
+					"memory",
+					&DebugMemory {
+						live: contents.addresses.len(),
+						holes: Some(contents.holes.len()),
+						allocated_bytes: contents.memory.allocated_bytes(),
+					},
+				)
+				.finish()
+		} else {
+			entries.fmt(f)
+		}
+	}
+}
+
+/// Formats like a [`BTreeMap`].
+///
+/// The alternate form (`{:#?}`) additionally includes a `memory` section with
+/// the number of live entries and the [`allocated_bytes`](`PressedPineMap::allocated_bytes`).
+#[allow(clippy::missing_fields_in_debug)] // The fields are formatted through the lock.
+impl<K: Ord + Debug, V: ?Sized + Debug> Debug for PressedPineMap<K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let contents = self.contents.read(/* poisoned */);
+		let entries = DebugEntries(&contents.addresses);
+		if f.alternate() {
+			f.debug_struct("PressedPineMap")
+				.field("entries", &entries)
+				.field(
+					"memory",
+					&DebugMemory {
+						live: contents.addresses.len(),
+						holes: None,
+						allocated_bytes: contents.memory.allocated_bytes(),
+					},
+				)
+				.finish()
+		} else {
+			entries.fmt(f)
+		}
+	}
+}
+
+/// Compares only the entries, in key order.
+///
+/// Holes, arena layout and insertion history don't affect the result.
+///
+/// Both instances are read-locked for the duration of the comparison.
+impl<K: Ord, V: PartialEq> PartialEq for PineMap<K, V> {
+	fn eq(&self, other: &Self) -> bool {
+		ptr::eq(self, other)
+			|| entries_eq(
+				&self.contents.read(/* poisoned */).addresses,
+				&other.contents.read(/* poisoned */).addresses,
+			)
+	}
+}
+
+impl<K: Ord, V: Eq> Eq for PineMap<K, V> {}
+
+/// Hashes only the entries, in key order, consistently with [`PartialEq`].
+impl<K: Ord + Hash, V: Hash> Hash for PineMap<K, V> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		hash_entries(&self.contents.read(/* poisoned */).addresses, state);
+	}
+}
+
+/// Compares only the entries, in key order.
+///
+/// Both instances are read-locked for the duration of the comparison.
+impl<K: Ord, V: ?Sized + PartialEq> PartialEq for PressedPineMap<K, V> {
+	fn eq(&self, other: &Self) -> bool {
+		ptr::eq(self, other)
+			|| entries_eq(
+				&self.contents.read(/* poisoned */).addresses,
+				&other.contents.read(/* poisoned */).addresses,
+			)
+	}
+}
+
+impl<K: Ord, V: ?Sized + Eq> Eq for PressedPineMap<K, V> {}
+
+/// Hashes only the entries, in key order, consistently with [`PartialEq`].
+impl<K: Ord + Hash, V: ?Sized + Hash> Hash for PressedPineMap<K, V> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		hash_entries(&self.contents.read(/* poisoned */).addresses, state);
+	}
+}
+
+fn entries_eq<K: Eq, V: ?Sized + PartialEq>(
+	a: &BTreeMap<K, *mut V>,
+	b: &BTreeMap<K, *mut V>,
+) -> bool {
+	a.len() == b.len()
+		&& a.iter()
+			.zip(b.iter())
+			.all(|((a_key, a_value), (b_key, b_value))| {
+				a_key == b_key && unsafe { **a_value == **b_value }
+			})
+}
+
+fn hash_entries<K: Hash, V: ?Sized + Hash, H: Hasher>(
+	addresses: &BTreeMap<K, *mut V>,
+	state: &mut H,
+) {
+	addresses.len().hash(state);
+	for (key, value) in addresses {
+		key.hash(state);
+		unsafe { &**value }.hash(state);
+	}
+}
+
+struct DebugEntries<'a, K, V: ?Sized>(&'a BTreeMap<K, *mut V>);
+
+impl<K: Debug, V: ?Sized + Debug> Debug for DebugEntries<'_, K, V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_map()
+			.entries(self.0.iter().map(|(key, value)| (key, unsafe { &**value })))
+			.finish()
 	}
 }
 
-impl<K: Ord, V: ?Sized> PressedPineMap<K, V> {
-	/// Creates a new empty [`PressedPineMap`].
-	#[must_use]
-	pub fn new() -> Self {
-		Self {
-			contents: RwLock::new(PressedCambium {
-				addresses: BTreeMap::new(),
-				memory: Bump::new(),
-			}),
-		}
-	}
+struct DebugMemory {
+	live: usize,
+	// `PressedPineMap` doesn't track holes.
+	holes: Option<usize>,
+	allocated_bytes: usize,
+}
 
-	/// Creates a new empty [`PressedPineMap`] that will store values (almost) contiguously
-	/// until `capacity` (in bytes that are the size of a maximally aligned buffer!) are exceeded.
-	#[must_use]
-	pub fn with_capacity(capacity_bytes: usize) -> Self {
-		Self {
-			contents: RwLock::new(PressedCambium {
-				addresses: BTreeMap::new(),
-				memory: Bump::with_capacity(capacity_bytes),
-			}),
+impl Debug for DebugMemory {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut memory = f.debug_struct("Memory");
+		memory.field("live", &self.live);
+		if let Some(holes) = &self.holes {
+			memory.field("holes", holes);
 		}
+		memory
+			.field("allocated_bytes", &self.allocated_bytes)
+			.finish()
 	}
 }
 
-impl<K: Ord, V> Default for PineMap<K, V> {
-	fn default() -> Self {
-		Self::new()
+/// Entries with duplicate keys are dropped, so the first of each wins.
+impl<K: Ord, V: ?Sized> FromIterator<(K, Box<V>)> for PressedPineMap<K, V> {
+	fn from_iter<T: IntoIterator<Item = (K, Box<V>)>>(iter: T) -> Self {
+		let map = Self::new();
+		for (key, value) in iter {
+			let _ = map.emplace_boxed(key, value);
+		}
+		map
 	}
 }
 
-impl<K: Ord, V: ?Sized> Default for PressedPineMap<K, V> {
-	fn default() -> Self {
-		Self::new()
+/// Entries whose keys are already present are discarded, like with [`.insert(…)`](`UnpinnedPineMap::insert`).
+///
+/// Slots for the lower bound of the iterator's [`size_hint`](`Iterator::size_hint`) are reserved contiguously up front
+/// (as with [`.reserve_holes(…)`](`PineMap::reserve_holes`)). Any further entries are allocated as usual.
+impl<K: Ord, V> Extend<(K, V)> for PineMap<K, V> {
+	fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+		let iter = iter.into_iter();
+		let mut contents = self.get_mut_counted();
+		let available = contents
+			.max_entries
+			.saturating_sub(contents.addresses.len());
+		contents.reserve_holes(iter.size_hint().0.min(available));
+		for (key, value) in iter {
+			let _ =
+				contents.try_emplace_with(key, |_, slot| Ok::<_, Infallible>(slot.write(value)));
+		}
 	}
 }
 
@@ -256,6 +2631,183 @@ impl<K: Ord, V> UnpinnedPineMap<K, V> for PineMap<K, V> {
 		contents.addresses.get(key).map(|value| unsafe { &**value })
 	}
 
+	fn len(&self) -> usize {
+		self.contents.read(/* poisoned */).addresses.len()
+	}
+
+	fn contains_key<Q>(&self, key: &Q) -> bool
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents.read(/* poisoned */).addresses.contains_key(key)
+	}
+
+	fn count_range<Q, R: RangeBounds<Q>>(&self, range: R) -> usize
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents.read(/* poisoned */).addresses.range(range).count()
+	}
+
+	fn range_as_map<Q, R: RangeBounds<Q>>(&self, range: R) -> BTreeMap<K, &V>
+	where
+		K: std::borrow::Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.range(range)
+			.map(|(key, value)| (key.clone(), unsafe { &**value }))
+			.collect()
+	}
+
+	fn range_mut<Q, R: RangeBounds<Q>>(&mut self, range: R) -> RangeMut<'_, K, V>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe {
+			RangeMut::new(self.contents.get_mut(/* poisoned */).addresses.range_mut(range))
+		}
+	}
+
+	fn contains_all<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: 'a + Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		keys.into_iter()
+			.all(|key| contents.addresses.contains_key(key))
+	}
+
+	fn contains_any<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: 'a + Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		keys.into_iter()
+			.any(|key| contents.addresses.contains_key(key))
+	}
+
+	fn get_key<Q>(&self, key: &Q) -> Option<K>
+	where
+		K: std::borrow::Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.get_key_value(key)
+			.map(|(key, _)| key.clone())
+	}
+
+	fn get_by_index(&self, index: usize) -> Option<(K, &V)>
+	where
+		K: Clone,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.iter()
+			.nth(index)
+			.map(|(key, value)| (key.clone(), unsafe { &**value }))
+	}
+
+	fn index_of<Q>(&self, key: &Q) -> Option<usize>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		index_of(&self.contents.read(/* poisoned */).addresses, key)
+	}
+
+	fn fold_values<B, F: FnMut(B, &V) -> B>(&self, init: B, mut f: F) -> B {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.fold(init, |acc, value| f(acc, unsafe { &**value }))
+	}
+
+	fn values_vec(&self) -> Vec<&V> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.map(|value| unsafe { &**value })
+			.collect()
+	}
+
+	fn to_vec(&self) -> Vec<(K, V)>
+	where
+		K: Copy,
+		V: Copy,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.iter()
+			.map(|(&key, &value)| (key, unsafe { *value }))
+			.collect()
+	}
+
+	fn first_value(&self) -> Option<&V> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.next()
+			.map(|value| unsafe { &**value })
+	}
+
+	fn last_value(&self) -> Option<&V> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.next_back()
+			.map(|value| unsafe { &**value })
+	}
+
+	fn keys(&self) -> Keys<'_, K, V>
+	where
+		K: Clone,
+	{
+		Keys::new(RwLockReadGuard::map(
+			self.contents.read(/* poisoned */),
+			|contents| &contents.addresses,
+		))
+	}
+
+	fn values(&self) -> Values<'_, K, V> {
+		unsafe {
+			Values::new(RwLockReadGuard::map(
+				self.contents.read(/* poisoned */),
+				|contents| &contents.addresses,
+			))
+		}
+	}
+
+	fn range<Q, R: RangeBounds<Q>>(&self, range: R) -> Range<'_, K, V>
+	where
+		K: std::borrow::Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		unsafe {
+			Range::new(
+				RwLockReadGuard::map(self.contents.read(/* poisoned */), |contents| {
+					&contents.addresses
+				}),
+				range,
+			)
+		}
+	}
+
 	fn try_insert_with<F: FnOnce(&K) -> Result<V, E>, E>(
 		&self,
 		key: K,
@@ -270,9 +2822,36 @@ impl<K: Ord, V> UnpinnedPineMap<K, V> for PineMap<K, V> {
 		.pipe(Ok)
 	}
 
+	#[allow(clippy::missing_panics_doc)] // Only unreachable panics.
+	fn insert_or_inspect<F: FnOnce(&V)>(
+		&self,
+		key: K,
+		value: V,
+		on_conflict: F,
+	) -> Fine<&V, (K, V)> {
+		let value = Cell::new(Some(value));
+		let mut contents = self.write_counted();
+		let (existing_or_new, result) = contents
+			.try_emplace_with(key, |_, slot| {
+				Ok::<_, Infallible>(slot.write(value.take().expect("unreachable")))
+			})
+			.unwrap_infallible();
+		let existing_or_new = unsafe { &*existing_or_new };
+		if result.is_err() {
+			on_conflict(existing_or_new);
+		}
+		drop(contents);
+		(
+			existing_or_new,
+			result.map_err(|(key, _)| (key, value.take().expect("unreachable"))),
+		)
+	}
+
 	/// Drops all keys and all values in this collection, even if some of them panic while being done so.
 	///
-	/// The drop order is unspecified and may change at any point (even between compilations or runs).
+	/// Entries are dropped in key order. Whether each key is dropped right before its value
+	/// or all values before all keys (or vice versa) depends on the instance's [`DropPhase`],
+	/// which is [`Interleaved`](`DropPhase::Interleaved`) unless set through [`PineMap::with_drop_phase`].
 	///
 	/// # Panics
 	///
@@ -282,82 +2861,342 @@ impl<K: Ord, V> UnpinnedPineMap<K, V> for PineMap<K, V> {
 	///
 	/// > That's probably not the ideal way to handle this. I'm taking suggestions.
 	fn clear(&mut self) {
-		let contents = self.contents.get_mut(/* poisoned */);
+		let mut contents = self.get_mut_counted();
 
+		// Holes point into `memory`, which is reset below even if dropping entries panics,
+		// so none of them may outlive this call. They never alias a live value,
+		// so it doesn't matter that they are discarded before the entries are dropped.
 		contents.holes.clear();
 
-		let success = if mem::needs_drop::<V>() {
-			catch_unwind(AssertUnwindSafe(|| {
-				drop_all_pinned(mem::take(&mut contents.addresses))
-			}))
-		} else {
-			contents.addresses.clear();
-			Ok(())
-		};
+		let drop_phase = contents.drop_phase;
+		let addresses = mem::take(&mut contents.addresses);
+		// Keys may panic while being dropped too, so the arena must be reset regardless.
+		let success = catch_unwind(AssertUnwindSafe(|| {
+			if mem::needs_drop::<V>() {
+				drop_all_pinned(addresses, drop_phase)
+			} else {
+				drop(addresses)
+			}
+		}));
+
+		// Only now that `addresses` is empty, the slots can be handed out again.
+		contents.memory.reset();
+
+		success.unwrap_or_else(|panic| panic::resume_unwind(panic));
+	}
+
+	fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.get_mut(/* poisoned */);
+		contents
+			.addresses
+			.get(key)
+			.map(|value| unsafe { &mut **value })
+	}
+
+	fn get_key_value_mut<Q>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.get_mut(/* poisoned */);
+		contents
+			.addresses
+			.get_key_value(key)
+			.map(|(key, value)| (key, unsafe { &mut **value }))
+	}
+
+	fn get_disjoint_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> [Option<&mut V>; N]
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		get_disjoint_mut(&mut self.contents.get_mut(/* poisoned */).addresses, keys)
+	}
+
+	fn try_insert_with_mut<F: FnOnce(&K) -> Result<V, E>, E>(
+		&mut self,
+		key: K,
+		value_factory: F,
+	) -> Result<Fine<&mut V, (K, F)>, E> {
+		let value_factory = Cell::new(Some(value_factory));
+		self.try_emplace_with_mut(key, |key, slot| {
+			slot.write(value_factory.take().expect("unreachable")(key)?)
+				.pipe(Ok)
+		})?
+		.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
+		.pipe(Ok)
+	}
+
+	fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F) -> usize {
+		let mut counted = self.get_mut_counted();
+		let Cambium {
+			addresses, holes, ..
+		} = &mut *counted;
+		retain_in_place(addresses, f, |value| holes.push(value.cast()))
+	}
+
+	fn truncate(&mut self, len: usize) {
+		let mut counted = self.get_mut_counted();
+		let Cambium {
+			addresses, holes, ..
+		} = &mut *counted;
+		if addresses.len() > len {
+			let mut kept = 0;
+			retain_in_place(
+				addresses,
+				|_, _| {
+					kept += 1;
+					kept <= len
+				},
+				|value| holes.push(value.cast()),
+			);
+		}
+	}
+
+	fn replace_key<Q>(&mut self, old: &Q, new: K) -> Result<Option<K>, K>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let addresses = &mut self.contents.get_mut(/* poisoned */).addresses;
+		if addresses.contains_key::<K>(&new) {
+			return Err(new);
+		}
+		Ok(addresses.remove_entry(old).map(|(old, value)| {
+			// Only the pointer is re-keyed, so the value stays where it is.
+			addresses.insert(new, value);
+			old
+		}))
+	}
+
+	fn try_for_each_mut<F: FnMut(&K, &mut V) -> Result<(), E>, E>(
+		&mut self,
+		mut f: F,
+	) -> Result<(), E> {
+		self.contents
+			.get_mut(/* poisoned */)
+			.addresses
+			.iter()
+			.try_for_each(|(key, value)| f(key, unsafe { &mut **value }))
+	}
+
+	fn remove_pair<Q>(&mut self, key: &Q) -> Option<(K, V)>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let mut contents = self.get_mut_counted();
+		let (key, value) = contents.addresses.remove_entry(key)?;
+		// The value must be moved out before its slot becomes available for reuse.
+		let value_read = unsafe { value.read() };
+		contents.holes.push(value.cast());
+		Some((key, value_read))
+	}
+
+	fn remove_key<Q>(&mut self, key: &Q) -> Option<K>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let mut contents = self.get_mut_counted();
+		let (key, value) = contents.addresses.remove_entry(key)?;
+		// Exclusive access rules out reuse until this returns,
+		// and this way the slot isn't lost if dropping the value panics.
+		contents.holes.push(value.cast());
+		unsafe { value.drop_in_place() };
+		Some(key)
+	}
+}
+
+impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
+	fn get<Q>(&self, key: &Q) -> Option<&V>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents.addresses.get(key).map(|value| unsafe { &**value })
+	}
+
+	fn len(&self) -> usize {
+		self.contents.read(/* poisoned */).addresses.len()
+	}
+
+	fn contains_key<Q>(&self, key: &Q) -> bool
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents.read(/* poisoned */).addresses.contains_key(key)
+	}
+
+	fn count_range<Q, R: RangeBounds<Q>>(&self, range: R) -> usize
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		self.contents.read(/* poisoned */).addresses.range(range).count()
+	}
+
+	fn range_as_map<Q, R: RangeBounds<Q>>(&self, range: R) -> BTreeMap<K, &V>
+	where
+		K: std::borrow::Borrow<Q> + Clone,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.range(range)
+			.map(|(key, value)| (key.clone(), unsafe { &**value }))
+			.collect()
+	}
 
-		contents.memory.reset();
+	fn range_mut<Q, R: RangeBounds<Q>>(&mut self, range: R) -> RangeMut<'_, K, V>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		unsafe {
+			RangeMut::new(self.contents.get_mut(/* poisoned */).addresses.range_mut(range))
+		}
+	}
 
-		success.unwrap_or_else(|panic| panic::resume_unwind(panic));
+	fn contains_all<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: 'a + Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		keys.into_iter()
+			.all(|key| contents.addresses.contains_key(key))
 	}
 
-	fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+	fn contains_any<'a, Q, I: IntoIterator<Item = &'a Q>>(&self, keys: I) -> bool
 	where
 		K: std::borrow::Borrow<Q>,
+		Q: 'a + Ord + ?Sized,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		keys.into_iter()
+			.any(|key| contents.addresses.contains_key(key))
+	}
+
+	fn get_key<Q>(&self, key: &Q) -> Option<K>
+	where
+		K: std::borrow::Borrow<Q> + Clone,
 		Q: Ord + ?Sized,
 	{
-		let contents = self.contents.get_mut(/* poisoned */);
+		let contents = self.contents.read(/* poisoned */);
 		contents
 			.addresses
-			.get(key)
-			.map(|value| unsafe { &mut **value })
+			.get_key_value(key)
+			.map(|(key, _)| key.clone())
 	}
 
-	fn try_insert_with_mut<F: FnOnce(&K) -> Result<V, E>, E>(
-		&mut self,
-		key: K,
-		value_factory: F,
-	) -> Result<Fine<&mut V, (K, F)>, E> {
-		let value_factory = Cell::new(Some(value_factory));
-		self.try_emplace_with_mut(key, |key, slot| {
-			slot.write(value_factory.take().expect("unreachable")(key)?)
-				.pipe(Ok)
-		})?
-		.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
-		.pipe(Ok)
+	fn get_by_index(&self, index: usize) -> Option<(K, &V)>
+	where
+		K: Clone,
+	{
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.iter()
+			.nth(index)
+			.map(|(key, value)| (key.clone(), unsafe { &**value }))
 	}
 
-	fn remove_pair<Q>(&mut self, key: &Q) -> Option<(K, V)>
+	fn index_of<Q>(&self, key: &Q) -> Option<usize>
 	where
 		K: std::borrow::Borrow<Q>,
 		Q: Ord + ?Sized,
 	{
-		let contents = self.contents.get_mut(/* poisoned */);
-		let (key, value) = contents.addresses.remove_entry(key)?;
-		contents.holes.push(value.cast());
-		Some((key, unsafe { value.read() }))
+		index_of(&self.contents.read(/* poisoned */).addresses, key)
 	}
 
-	fn remove_key<Q>(&mut self, key: &Q) -> Option<K>
+	fn fold_values<B, F: FnMut(B, &V) -> B>(&self, init: B, mut f: F) -> B {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.fold(init, |acc, value| f(acc, unsafe { &**value }))
+	}
+
+	fn values_vec(&self) -> Vec<&V> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.map(|value| unsafe { &**value })
+			.collect()
+	}
+
+	fn to_vec(&self) -> Vec<(K, V)>
 	where
-		K: std::borrow::Borrow<Q>,
-		Q: Ord + ?Sized,
+		K: Copy,
+		V: Copy,
 	{
-		let contents = self.contents.get_mut(/* poisoned */);
-		let (key, value) = contents.addresses.remove_entry(key)?;
-		contents.holes.push(value.cast());
-		unsafe { value.drop_in_place() };
-		Some(key)
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.iter()
+			.map(|(&key, &value)| (key, unsafe { *value }))
+			.collect()
 	}
-}
 
-impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
-	fn get<Q>(&self, key: &Q) -> Option<&V>
+	fn first_value(&self) -> Option<&V> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.next()
+			.map(|value| unsafe { &**value })
+	}
+
+	fn last_value(&self) -> Option<&V> {
+		let contents = self.contents.read(/* poisoned */);
+		contents
+			.addresses
+			.values()
+			.next_back()
+			.map(|value| unsafe { &**value })
+	}
+
+	fn keys(&self) -> Keys<'_, K, V>
 	where
-		K: std::borrow::Borrow<Q>,
+		K: Clone,
+	{
+		Keys::new(RwLockReadGuard::map(
+			self.contents.read(/* poisoned */),
+			|contents| &contents.addresses,
+		))
+	}
+
+	fn values(&self) -> Values<'_, K, V> {
+		unsafe {
+			Values::new(RwLockReadGuard::map(
+				self.contents.read(/* poisoned */),
+				|contents| &contents.addresses,
+			))
+		}
+	}
+
+	fn range<Q, R: RangeBounds<Q>>(&self, range: R) -> Range<'_, K, V>
+	where
+		K: std::borrow::Borrow<Q> + Clone,
 		Q: Ord + ?Sized,
 	{
-		let contents = self.contents.read(/* poisoned */);
-		contents.addresses.get(key).map(|value| unsafe { &**value })
+		unsafe {
+			Range::new(
+				RwLockReadGuard::map(self.contents.read(/* poisoned */), |contents| {
+					&contents.addresses
+				}),
+				range,
+			)
+		}
 	}
 
 	fn try_insert_with<F: FnOnce(&K) -> Result<V, E>, E>(
@@ -377,9 +3216,36 @@ impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
 		.pipe(Ok)
 	}
 
+	fn insert_or_inspect<F: FnOnce(&V)>(&self, key: K, value: V, on_conflict: F) -> Fine<&V, (K, V)>
+	where
+		V: Sized,
+	{
+		let mut contents = self.write_counted();
+		let PressedCambium {
+			addresses,
+			memory,
+			values_need_drop,
+			drop_phase: _,
+			generation: _,
+		} = &mut *contents;
+		#[allow(clippy::map_entry)]
+		if let Some(&existing_value) = addresses.get(&key) {
+			let existing_value = unsafe { &*existing_value };
+			on_conflict(existing_value);
+			(existing_value, Err((key, value)))
+		} else {
+			let value: *mut V = memory.alloc(value);
+			*values_need_drop |= mem::needs_drop::<V>();
+			addresses.insert(key, value);
+			(unsafe { &*value }, Ok(()))
+		}
+	}
+
 	/// Drops all keys and all values in this collection, even if some of them panic while being done so.
 	///
-	/// The drop order is unspecified and may change at any point (even between compilations or runs).
+	/// Entries are dropped in key order. Whether each key is dropped right before its value
+	/// or all values before all keys (or vice versa) depends on the instance's [`DropPhase`],
+	/// which is [`Interleaved`](`DropPhase::Interleaved`) unless set through [`PressedPineMap::with_drop_phase`].
 	///
 	/// # Panics
 	///
@@ -389,10 +3255,18 @@ impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
 	///
 	/// > That's probably not the ideal way to handle this. I'm taking suggestions.
 	fn clear(&mut self) {
-		let contents = self.contents.get_mut(/* poisoned */);
+		let mut contents = self.get_mut_counted();
 
+		let values_need_drop = mem::replace(&mut contents.values_need_drop, false);
+		let drop_phase = contents.drop_phase;
+		let addresses = mem::take(&mut contents.addresses);
+		// Keys may panic while being dropped too, so the arena must be reset regardless.
 		let success = catch_unwind(AssertUnwindSafe(|| {
-			drop_all_pinned(mem::take(&mut contents.addresses))
+			if values_need_drop {
+				drop_all_pinned(addresses, drop_phase)
+			} else {
+				drop(addresses)
+			}
 		}));
 
 		contents.memory.reset();
@@ -412,6 +3286,26 @@ impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
 			.map(|value| unsafe { &mut **value })
 	}
 
+	fn get_key_value_mut<Q>(&mut self, key: &Q) -> Option<(&K, &mut V)>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let contents = self.contents.get_mut(/* poisoned */);
+		contents
+			.addresses
+			.get_key_value(key)
+			.map(|(key, value)| (key, unsafe { &mut **value }))
+	}
+
+	fn get_disjoint_mut<Q, const N: usize>(&mut self, keys: [&Q; N]) -> [Option<&mut V>; N]
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		get_disjoint_mut(&mut self.contents.get_mut(/* poisoned */).addresses, keys)
+	}
+
 	fn try_insert_with_mut<F: FnOnce(&K) -> Result<V, E>, E>(
 		&mut self,
 		key: K,
@@ -429,13 +3323,59 @@ impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
 		.pipe(Ok)
 	}
 
+	fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F) -> usize {
+		retain_in_place(&mut self.get_mut_counted().addresses, f, |_| ())
+	}
+
+	fn truncate(&mut self, len: usize) {
+		let mut counted = self.get_mut_counted();
+		if counted.addresses.len() > len {
+			let mut kept = 0;
+			retain_in_place(
+				&mut counted.addresses,
+				|_, _| {
+					kept += 1;
+					kept <= len
+				},
+				|_| (),
+			);
+		}
+	}
+
+	fn replace_key<Q>(&mut self, old: &Q, new: K) -> Result<Option<K>, K>
+	where
+		K: std::borrow::Borrow<Q>,
+		Q: Ord + ?Sized,
+	{
+		let addresses = &mut self.contents.get_mut(/* poisoned */).addresses;
+		if addresses.contains_key::<K>(&new) {
+			return Err(new);
+		}
+		Ok(addresses.remove_entry(old).map(|(old, value)| {
+			// Only the pointer is re-keyed, so the value stays where it is.
+			addresses.insert(new, value);
+			old
+		}))
+	}
+
+	fn try_for_each_mut<F: FnMut(&K, &mut V) -> Result<(), E>, E>(
+		&mut self,
+		mut f: F,
+	) -> Result<(), E> {
+		self.contents
+			.get_mut(/* poisoned */)
+			.addresses
+			.iter()
+			.try_for_each(|(key, value)| f(key, unsafe { &mut **value }))
+	}
+
 	fn remove_pair<Q>(&mut self, key: &Q) -> Option<(K, V)>
 	where
 		V: Sized,
 		K: std::borrow::Borrow<Q>,
 		Q: Ord + ?Sized,
 	{
-		let contents = self.contents.get_mut(/* poisoned */);
+		let mut contents = self.get_mut_counted();
 		let (key, value) = contents.addresses.remove_entry(key)?;
 		Some((key, unsafe { value.read() }))
 	}
@@ -445,7 +3385,7 @@ impl<K: Ord, V: ?Sized> UnpinnedPineMap<K, V> for PressedPineMap<K, V> {
 		K: std::borrow::Borrow<Q>,
 		Q: Ord + ?Sized,
 	{
-		let contents = self.contents.get_mut(/* poisoned */);
+		let mut contents = self.get_mut_counted();
 		let (key, value) = contents.addresses.remove_entry(key)?;
 		unsafe { value.drop_in_place() };
 		Some(key)
@@ -468,27 +3408,10 @@ impl<K: Ord, V> UnpinnedPineMapEmplace<K, V, V> for PineMap<K, V> {
 		key: K,
 		value_factory: F,
 	) -> Result<Fine<&V, (K, F)>, E> {
-		let mut contents = self.contents.write(/* poisoned */);
-		let Cambium {
-			addresses,
-			memory,
-			holes,
-		} = &mut *contents;
-		#[allow(clippy::map_entry)]
-		if let Some(existing_value) = addresses.get(&key) {
-			(unsafe { &**existing_value }, Err((key, value_factory)))
-		} else if let Some(hole) = holes.pop() {
-			let slot = unsafe { &mut *hole };
-			let value = value_factory(&key, slot).tap_err(|_| holes.push(hole))?;
-			addresses.insert(key, value as *mut _);
-			(&*value, Ok(()))
-		} else {
-			let value = value_factory(&key, memory.alloc(MaybeUninit::uninit()))?;
-			addresses.insert(key, value as *mut _);
-			(&*value, Ok(()))
-		}
-		.map(|value| unsafe { &*(value as *const _) })
-		.pipe(Ok)
+		self.write_counted()
+			.try_emplace_with(key, value_factory)?
+			.map(|value| unsafe { &*value })
+			.pipe(Ok)
 	}
 
 	fn try_emplace_with_mut<
@@ -499,26 +3422,28 @@ impl<K: Ord, V> UnpinnedPineMapEmplace<K, V, V> for PineMap<K, V> {
 		key: K,
 		value_factory: F,
 	) -> Result<Fine<&mut V, (K, F)>, E> {
-		let Cambium {
-			addresses,
-			memory,
-			holes,
-		} = self.contents.get_mut();
-		#[allow(clippy::map_entry)]
-		if let Some(existing_value) = addresses.get(&key) {
-			(unsafe { &mut **existing_value }, Err((key, value_factory)))
-		} else if let Some(hole) = holes.pop() {
-			let slot = unsafe { &mut *hole };
-			let value = value_factory(&key, slot).tap_err(|_| holes.push(hole))?;
-			addresses.insert(key, value as *mut _);
-			(value, Ok(()))
-		} else {
-			let value = value_factory(&key, memory.alloc(MaybeUninit::uninit()))?;
-			addresses.insert(key, value as *mut _);
-			(value, Ok(()))
-		}
-		.map(|value| unsafe { &mut *(value as *mut _) })
-		.pipe(Ok)
+		self.get_mut_counted()
+			.try_emplace_with(key, value_factory)?
+			.map(|value| unsafe { &mut *value })
+			.pipe(Ok)
+	}
+
+	#[allow(clippy::missing_panics_doc)] // Only unreachable panics.
+	fn emplace_with_index<F: for<'a> FnOnce(&K, usize, &'a mut MaybeUninit<V>) -> &'a mut V>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Fine<&V, (K, F)> {
+		let mut contents = self.write_counted();
+		let index = contents.addresses.range(..&key).count();
+		let value_factory = Cell::new(Some(value_factory));
+		contents
+			.try_emplace_with(key, |key, slot| {
+				Ok::<_, Infallible>(value_factory.take().expect("unreachable")(key, index, slot))
+			})
+			.unwrap_infallible()
+			.map(|value| unsafe { &*value })
+			.map_err(|(key, _)| (key, value_factory.take().expect("unreachable")))
 	}
 }
 
@@ -531,14 +3456,24 @@ impl<K: Ord, V: ?Sized, W> UnpinnedPineMapEmplace<K, V, W> for PressedPineMap<K,
 		key: K,
 		value_factory: F,
 	) -> Result<Fine<&V, (K, F)>, E> {
-		let mut contents = self.contents.write(/* poisoned */);
-		let PressedCambium { addresses, memory } = &mut *contents;
+		let mut contents = self.write_counted();
+		let PressedCambium {
+			addresses,
+			memory,
+			values_need_drop,
+			drop_phase: _,
+			generation: _,
+		} = &mut *contents;
 		#[allow(clippy::map_entry)]
 		if let Some(existing_value) = addresses.get(&key) {
 			(unsafe { &**existing_value }, Err((key, value_factory)))
 		} else {
-			let value = value_factory(&key, memory.alloc(MaybeUninit::uninit()))?;
-			addresses.insert(key, value as *mut _);
+			let entry = vacant_entry(addresses, key);
+			let slot = memory.alloc(MaybeUninit::uninit());
+			let slot_address: *const MaybeUninit<W> = slot;
+			let value = value_factory(entry.key(), slot)?;
+			*values_need_drop |= emplaced_needs_drop(slot_address, value);
+			entry.insert(value as *mut _);
 			(unsafe { &*(value as *const _) }, Ok(()))
 		}
 		.pipe(Ok)
@@ -552,17 +3487,56 @@ impl<K: Ord, V: ?Sized, W> UnpinnedPineMapEmplace<K, V, W> for PressedPineMap<K,
 		key: K,
 		value_factory: F,
 	) -> Result<Fine<&mut V, (K, F)>, E> {
-		let PressedCambium { addresses, memory } = self.contents.get_mut(/* poisoned */);
+		let mut contents = self.get_mut_counted();
+		let PressedCambium {
+			addresses,
+			memory,
+			values_need_drop,
+			drop_phase: _,
+			generation: _,
+		} = &mut *contents;
 		#[allow(clippy::map_entry)]
 		if let Some(existing_value) = addresses.get(&key) {
 			(unsafe { &mut **existing_value }, Err((key, value_factory)))
 		} else {
-			let value = value_factory(&key, memory.alloc(MaybeUninit::uninit()))?;
-			addresses.insert(key, value as *mut _);
+			let entry = vacant_entry(addresses, key);
+			let slot = memory.alloc(MaybeUninit::uninit());
+			let slot_address: *const MaybeUninit<W> = slot;
+			let value = value_factory(entry.key(), slot)?;
+			*values_need_drop |= emplaced_needs_drop(slot_address, value);
+			entry.insert(value as *mut _);
 			(unsafe { &mut *(value as *mut _) }, Ok(()))
 		}
 		.pipe(Ok)
 	}
+
+	fn emplace_with_index<F: for<'a> FnOnce(&K, usize, &'a mut MaybeUninit<W>) -> &'a mut V>(
+		&self,
+		key: K,
+		value_factory: F,
+	) -> Fine<&V, (K, F)> {
+		let mut contents = self.write_counted();
+		let PressedCambium {
+			addresses,
+			memory,
+			values_need_drop,
+			drop_phase: _,
+			generation: _,
+		} = &mut *contents;
+		#[allow(clippy::map_entry)]
+		if let Some(existing_value) = addresses.get(&key) {
+			(unsafe { &**existing_value }, Err((key, value_factory)))
+		} else {
+			let index = addresses.range(..&key).count();
+			let entry = vacant_entry(addresses, key);
+			let slot = memory.alloc(MaybeUninit::uninit());
+			let slot_address: *const MaybeUninit<W> = slot;
+			let value = value_factory(entry.key(), index, slot);
+			*values_need_drop |= emplaced_needs_drop(slot_address, value);
+			entry.insert(value as *mut _);
+			(unsafe { &*(value as *const _) }, Ok(()))
+		}
+	}
 }
 
 unsafe impl<K: Ord, V> PinnedPineMap<K, V> for Pin<PineMap<K, V>> {
@@ -601,9 +3575,26 @@ where
 {
 }
 
+// Handles only dereference through the instance they were obtained from.
+unsafe impl<V: ?Sized> Send for PinnedHandle<V> {}
+unsafe impl<V: ?Sized> Sync for PinnedHandle<V> {}
+
+// Iterators that own (or drop) entries, much like `btree_map::IntoIter`.
+unsafe impl<K: Send, V: ?Sized + Send> Send for IntoIter<K, V> {}
+unsafe impl<K: Ord + Send, V: Send> Send for DrainRange<'_, K, V> {}
+unsafe impl<K: Ord + Send, V: Send, F: FnMut(&K) -> bool + Send> Send
+	for ExtractKeysIf<'_, K, V, F>
+{
+}
+
+// `IterDowncast` isn't `Send`, as the lock must be released on the thread that acquired it.
+unsafe impl<K: Ord + Sync, T: Sync> Sync for IterDowncast<'_, K, T> {}
+
 /// Drops all keys and all values in this collection, even if some of them panic while being done so.
 ///
-/// The drop order is unspecified and may change at any point (even between compilations or runs).
+/// Entries are dropped in key order. Whether each key is dropped right before its value
+/// or all values before all keys (or vice versa) depends on the instance's [`DropPhase`],
+/// which is [`Interleaved`](`DropPhase::Interleaved`) unless set through [`PineMap::with_drop_phase`].
 ///
 /// # Panics
 ///
@@ -617,19 +3608,23 @@ impl<K: Ord, V> Drop for PineMap<K, V> {
 		// None of the data will be used in the future,
 		// so explicit cleanup can be a bit more concise (and hopefully a little faster) than calling `.clean()`.
 
+		// The key index (and with it the keys) and the arena are then released as the fields are dropped.
 		if !mem::needs_drop::<V>() {
 			return;
 		}
 
 		let contents = self.contents.get_mut(/* poisoned */);
 
-		drop_all_pinned(mem::take(&mut contents.addresses));
+		// `mem::take` doesn't allocate here, as `BTreeMap::new()` doesn't.
+		drop_all_pinned(mem::take(&mut contents.addresses), contents.drop_phase);
 	}
 }
 
 /// Drops all keys and all values in this collection, even if some of them panic while being done so.
 ///
-/// The drop order is unspecified and may change at any point (even between compilations or runs).
+/// Entries are dropped in key order. Whether each key is dropped right before its value
+/// or all values before all keys (or vice versa) depends on the instance's [`DropPhase`],
+/// which is [`Interleaved`](`DropPhase::Interleaved`) unless set through [`PressedPineMap::with_drop_phase`].
 ///
 /// # Panics
 ///
@@ -644,19 +3639,297 @@ impl<K: Ord, V: ?Sized> Drop for PressedPineMap<K, V> {
 		// so explicit cleanup can be a bit more concise (and hopefully a little faster) than calling `.clean()`.
 
 		let contents = self.contents.get_mut(/* poisoned */);
+		if !contents.values_need_drop {
+			return;
+		}
+
+		drop_all_pinned(mem::take(&mut contents.addresses), contents.drop_phase);
+	}
+}
+
+fn index_of<K: Ord + Borrow<Q>, V: ?Sized, Q: Ord + ?Sized>(
+	addresses: &BTreeMap<K, *mut V>,
+	key: &Q,
+) -> Option<usize> {
+	addresses.contains_key(key).then(|| {
+		addresses
+			.range((Bound::Unbounded, Bound::Excluded(key)))
+			.count()
+	})
+}
+
+/// Only call this after checking that `key` is absent.
+fn vacant_entry<K: Ord, V: ?Sized>(
+	addresses: &mut BTreeMap<K, *mut V>,
+	key: K,
+) -> btree_map::VacantEntry<'_, K, *mut V> {
+	match addresses.entry(key) {
+		btree_map::Entry::Vacant(entry) => entry,
+		btree_map::Entry::Occupied(_) => unreachable!(),
+	}
+}
+
+fn get_disjoint_mut<'a, K: Ord + Borrow<Q>, V: ?Sized, Q: Ord + ?Sized, const N: usize>(
+	addresses: &'a mut BTreeMap<K, *mut V>,
+	keys: [&Q; N],
+) -> [Option<&'a mut V>; N] {
+	for (i, key) in keys.iter().enumerate() {
+		assert!(
+			keys[..i]
+				.iter()
+				.all(|other| other.cmp(key) != Ordering::Equal),
+			"Tried to get overlapping entries mutably."
+		);
+	}
+
+	// `Ord` may be implemented inconsistently, so the entries themselves must be checked for overlap too.
+	let values = keys.map(|key| addresses.get(key).copied());
+	let span = |value: *mut V| {
+		let start = value.cast::<u8>() as usize;
+		start..start + mem::size_of_val(unsafe { &*value })
+	};
+	for (i, value) in values.iter().enumerate() {
+		if let Some(value) = *value {
+			let value = span(value);
+			assert!(
+				values[..i].iter().flatten().all(|&other| {
+					let other = span(other);
+					// Zero-sized values never overlap.
+					value.end <= other.start || other.end <= value.start
+				}),
+				"Tried to get overlapping entries mutably."
+			);
+		}
+	}
+
+	// None of these alias, as checked above.
+	values.map(|value| value.map(|value| unsafe { &mut *value }))
+}
+
+/// Moves a (possibly unsized) value into a new [`Box`].
+///
+/// # Safety
+///
+/// `value` must point to a valid `V`, which is treated as moved-from afterwards.
+unsafe fn move_into_box<V: ?Sized>(value: *mut V) -> Box<V> {
+	let layout = Layout::for_value(&*value);
+	let memory = if layout.size() == 0 {
+		// Dangling, but well-aligned.
+		layout.align() as *mut u8
+	} else {
+		let memory = alloc::alloc(layout);
+		if memory.is_null() {
+			alloc::handle_alloc_error(layout)
+		}
+		memory
+	};
+	value
+		.cast::<u8>()
+		.copy_to_nonoverlapping(memory, layout.size());
+	Box::from_raw(with_address(value, memory))
+}
+
+/// Roughly estimates the heap size of a `BTreeMap<K, *mut V>` with `len` entries.
+fn estimate_index_bytes<K, V: ?Sized>(len: usize) -> usize {
+	// `BTreeMap`'s current node capacity, which is an implementation detail.
+	const NODE_CAPACITY: usize = 11;
+
+	// Keys, values, and the parent pointer plus two indices.
+	let node_bytes = NODE_CAPACITY * (mem::size_of::<K>() + mem::size_of::<*mut V>())
+		+ mem::size_of::<usize>() * 2;
+	if len == 0 {
+		return 0;
+	}
+	// Plus one for the root or a partially filled node.
+	let nodes = len * 3 / (NODE_CAPACITY * 2) + 1;
+	nodes * node_bytes
+}
+
+/// Checks whether a value emplaced into a slot for a `W` may need dropping.
+///
+/// A factory may place the value elsewhere, in which case its drop glue is unknown.
+fn emplaced_needs_drop<W, V: ?Sized>(slot: *const MaybeUninit<W>, value: *const V) -> bool {
+	mem::needs_drop::<W>() || value.cast::<u8>() != slot.cast::<u8>()
+}
+
+/// Moves a (possibly unsized) value out of its [`Box`] and into `memory`.
+fn move_out_of_box<V: ?Sized>(value: Box<V>, memory: &Bump) -> *mut V {
+	let layout = Layout::for_value(&*value);
+	let value = Box::into_raw(value);
+	let target = memory.alloc_layout(layout).as_ptr();
+	unsafe {
+		value
+			.cast::<u8>()
+			.copy_to_nonoverlapping(target, layout.size());
+		if layout.size() != 0 {
+			alloc::dealloc(value.cast(), layout);
+		}
+		with_address(value, target)
+	}
+}
+
+/// Replaces the address of a (possibly wide) pointer, keeping its metadata.
+unsafe fn with_address<V: ?Sized>(mut pointer: *mut V, address: *mut u8) -> *mut V {
+	// The address is the first part of wide pointers on all supported targets.
+	// WAITING ON: <https://github.com/rust-lang/rust/issues/75091> (`<*mut T>::set_ptr_value`)
+	ptr::addr_of_mut!(pointer).cast::<*mut u8>().write(address);
+	pointer
+}
+
+/// Removes the entries for which `f` returns `false`, releases their values and then drops those in place.
+///
+/// Returns how many entries were removed.
+fn retain_in_place<K: Ord, V: ?Sized>(
+	addresses: &mut BTreeMap<K, *mut V>,
+	mut f: impl FnMut(&K, &mut V) -> bool,
+	mut release: impl FnMut(*mut V),
+) -> usize {
+	let mut removed = vec![];
+	let retained = catch_unwind(AssertUnwindSafe(|| {
+		addresses.retain(|key, value| {
+			f(key, unsafe { &mut **value }) || {
+				removed.push(*value);
+				false
+			}
+		})
+	}));
+
+	let count = removed.len();
+	// Exclusive access rules out reuse until this returns.
+	for &value in &removed {
+		release(value);
+	}
+	let dropped = catch_unwind(AssertUnwindSafe(|| {
+		drop_all_pinned(
+			removed.into_iter().map(|value| ((), value)),
+			DropPhase::Interleaved,
+		)
+	}));
+
+	retained
+		.and(dropped)
+		.unwrap_or_else(|panic| panic::resume_unwind(panic));
+	count
+}
+
+/// Counts `memory`'s chunks, but only if there's an `observer` to notify of growth.
+fn chunk_count(memory: &Bump, observer: Option<&(dyn Fn(usize) + Send + Sync)>) -> usize {
+	// The chunk iterator only reads the chunk footers, and nothing is allocated meanwhile.
+	observer.map_or(0, |_| unsafe { memory.iter_allocated_chunks_raw() }.count())
+}
+
+/// Calls `observer` with the new [`allocated_bytes`](`Bump::allocated_bytes`) iff `memory` grew beyond `chunk_count` chunks.
+fn notify_growth(
+	memory: &Bump,
+	chunk_count: usize,
+	observer: Option<&(dyn Fn(usize) + Send + Sync)>,
+) {
+	if let Some(observer) = observer {
+		if unsafe { memory.iter_allocated_chunks_raw() }.count() > chunk_count {
+			observer(memory.allocated_bytes());
+		}
+	}
+}
+
+/// A value pointer that may be dropped through on another thread.
+#[cfg(feature = "rayon")]
+struct SendPtr<V: ?Sized>(*mut V);
+#[cfg(feature = "rayon")]
+unsafe impl<V: ?Sized + Send> Send for SendPtr<V> {}
 
-		drop_all_pinned(mem::take(&mut contents.addresses));
+#[cfg(feature = "rayon")]
+fn par_drop_all<K: Send, V: Send>(entries: BTreeMap<K, *mut V>, drop_phase: DropPhase) {
+	use rayon::prelude::*;
+
+	fn catch(f: impl FnOnce()) -> Option<Box<dyn Any + Send>> {
+		catch_unwind(AssertUnwindSafe(f)).err()
+	}
+
+	let entries = entries
+		.into_iter()
+		.map(|(key, value)| (key, SendPtr(value)))
+		.collect::<Vec<_>>();
+
+	let panics: Vec<_> = match drop_phase {
+		DropPhase::Interleaved => entries
+			.into_par_iter()
+			.flat_map(|(key, value)| {
+				catch(|| drop(key))
+					.into_iter()
+					.chain(catch(|| unsafe { value.0.drop_in_place() }))
+					.collect::<Vec<_>>()
+			})
+			.collect(),
+		DropPhase::ValuesThenKeys => {
+			let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+			let mut panics: Vec<_> = values
+				.into_par_iter()
+				.filter_map(|value| catch(|| unsafe { value.0.drop_in_place() }))
+				.collect();
+			panics.par_extend(keys.into_par_iter().filter_map(|key| catch(|| drop(key))));
+			panics
+		}
+		DropPhase::KeysThenValues => {
+			let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+			let mut panics: Vec<_> = keys
+				.into_par_iter()
+				.filter_map(|key| catch(|| drop(key)))
+				.collect();
+			panics.par_extend(
+				values
+					.into_par_iter()
+					.filter_map(|value| catch(|| unsafe { value.0.drop_in_place() })),
+			);
+			panics
+		}
+	};
+	match panics.len() {
+		0 => (),
+		1 => panic::resume_unwind(panics.into_iter().next().expect("unreachable")),
+		_ => panic::resume_unwind(Box::new(panics)),
 	}
 }
 
-fn drop_all_pinned<K, V: ?Sized>(addresses: BTreeMap<K, *mut V>) {
+fn drop_all_pinned<K, V: ?Sized>(
+	entries: impl IntoIterator<Item = (K, *mut V)>,
+	drop_phase: DropPhase,
+) {
 	let mut panics = vec![];
+	let drop_key = |key: K, panics: &mut Vec<_>| {
+		catch_unwind(AssertUnwindSafe(|| drop(key))).unwrap_or_else(|panic| panics.push(panic))
+	};
+	let drop_value = |value: *mut V, panics: &mut Vec<_>| {
+		catch_unwind(AssertUnwindSafe(|| unsafe { value.drop_in_place() }))
+			.unwrap_or_else(|panic| panics.push(panic))
+	};
 
 	// WAITING ON: <https://github.com/rust-lang/rust/issues/70530> (`BTreeMap::drain_filter`)
-	for (key, value) in addresses {
-		catch_unwind(AssertUnwindSafe(|| drop(key))).unwrap_or_else(|panic| panics.push(panic));
-		catch_unwind(AssertUnwindSafe(|| unsafe { value.drop_in_place() }))
-			.unwrap_or_else(|panic| panics.push(panic));
+	match drop_phase {
+		DropPhase::Interleaved => {
+			for (key, value) in entries {
+				drop_key(key, &mut panics);
+				drop_value(value, &mut panics);
+			}
+		}
+		DropPhase::ValuesThenKeys => {
+			let entries = entries.into_iter().collect::<Vec<_>>();
+			for &(_, value) in &entries {
+				drop_value(value, &mut panics);
+			}
+			for (key, _) in entries {
+				drop_key(key, &mut panics);
+			}
+		}
+		DropPhase::KeysThenValues => {
+			let mut values = vec![];
+			for (key, value) in entries {
+				drop_key(key, &mut panics);
+				values.push(value);
+			}
+			for value in values {
+				drop_value(value, &mut panics);
+			}
+		}
 	}
 	match panics.len() {
 		0 => (),
@@ -0,0 +1,70 @@
+//! Kept separate from the other tests, as the counting allocator is process-wide.
+
+use pinus::{
+	prelude::*,
+	sync::{PineMap, PressedPineMap},
+};
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use this_is_fine::prelude::*;
+
+struct Counting;
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		if COUNTING.load(Ordering::SeqCst) {
+			ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+		}
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		if COUNTING.load(Ordering::SeqCst) {
+			ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+		}
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+	ALLOCATIONS.store(0, Ordering::SeqCst);
+	COUNTING.store(true, Ordering::SeqCst);
+	f();
+	COUNTING.store(false, Ordering::SeqCst);
+	ALLOCATIONS.load(Ordering::SeqCst)
+}
+
+#[test]
+fn pod_drops_dont_allocate() {
+	for _ in 0..10 {
+		let map = PineMap::new();
+		for i in 0..100_u64 {
+			map.insert(i, i).unwrap();
+		}
+		assert_eq!(allocations_during(|| drop(map)), 0);
+
+		let pressed = PressedPineMap::<_, [u8]>::new();
+		for i in 0..100_u64 {
+			pressed.push_slice(i, &[0; 8]).unwrap();
+		}
+		assert_eq!(allocations_during(|| drop(pressed)), 0);
+	}
+
+	let map = PineMap::new();
+	for i in 0..100_u64 {
+		map.insert(i, i.to_string()).unwrap();
+	}
+	assert_eq!(allocations_during(|| drop(map)), 0);
+}
@@ -1,9 +1,9 @@
 use pinus::{
 	prelude::*,
-	sync::{PineMap, PressedPineMap},
+	sync::{CapacityExceeded, Entry, PineMap, PressedPineMap, TryReserveError},
 };
 use static_assertions::assert_impl_all;
-use std::{error::Error, marker::PhantomPinned};
+use std::{convert::Infallible, error::Error, marker::PhantomPinned, pin::Pin};
 use this_is_fine::prelude::*;
 
 #[test]
@@ -73,3 +73,2707 @@ fn complicated() {
 
 assert_impl_all!(PineMap<PhantomPinned, PhantomPinned>: Unpin);
 assert_impl_all!(PressedPineMap<PhantomPinned, PhantomPinned>: Unpin);
+
+mod iterator_threading {
+	use pinus::{
+		sync::{DrainRange, ExtractKeysIf, IterDowncast},
+		Iter, Keys, RangeMut, RangePinnedMut, Values,
+	};
+	use static_assertions::{assert_impl_all, assert_not_impl_any};
+	use std::{cell::Cell, rc::Rc};
+
+	type Predicate = fn(&u8) -> bool;
+
+	assert_impl_all!(Iter<'static, u8, [u8]>: Sync);
+	assert_impl_all!(Keys<'static, u8, [u8]>: Sync);
+	assert_impl_all!(Values<'static, u8, [u8]>: Sync);
+	assert_impl_all!(IterDowncast<'static, u8, u8>: Sync);
+	assert_not_impl_any!(Iter<'static, u8, [u8]>: Send);
+	assert_not_impl_any!(Keys<'static, u8, [u8]>: Send);
+	assert_not_impl_any!(Values<'static, u8, [u8]>: Send);
+	assert_not_impl_any!(IterDowncast<'static, u8, u8>: Send);
+	assert_not_impl_any!(Values<'static, u8, Cell<u8>>: Sync);
+
+	assert_impl_all!(RangeMut<'static, u8, [u8]>: Send, Sync);
+	assert_impl_all!(RangePinnedMut<'static, u8, [u8]>: Send, Sync);
+	assert_impl_all!(RangeMut<'static, u8, Cell<u8>>: Send);
+	assert_not_impl_any!(RangeMut<'static, u8, Cell<u8>>: Sync);
+	assert_not_impl_any!(RangeMut<'static, Cell<u8>, u8>: Send, Sync);
+
+	assert_impl_all!(DrainRange<'static, u8, Cell<u8>>: Send);
+	assert_impl_all!(ExtractKeysIf<'static, u8, Cell<u8>, Predicate>: Send);
+	assert_not_impl_any!(DrainRange<'static, u8, Rc<u8>>: Send);
+}
+
+#[test]
+fn write_guard() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	let map = PineMap::<usize, AtomicUsize>::new();
+	let early = map.insert(0, AtomicUsize::new(0)).unwrap();
+
+	let mut guard = map.write();
+	for i in 0..10 {
+		if !guard.contains_key(&(i % 4)) {
+			guard.insert(i % 4, AtomicUsize::new(i)).unwrap();
+		}
+		guard
+			.get_or_insert_with(i % 4, |_| unreachable!())
+			.fetch_add(1, Ordering::Relaxed);
+	}
+	let one = guard.get(&1).unwrap();
+	assert!(guard.insert(1, AtomicUsize::new(100)).is_err());
+	drop(guard);
+
+	assert_eq!(early.load(Ordering::Relaxed), 3);
+	assert_eq!(one.load(Ordering::Relaxed), 1 + 3);
+	assert_eq!(map.get(&3).unwrap().load(Ordering::Relaxed), 3 + 2);
+}
+
+#[test]
+fn max_entries() {
+	let map = PineMap::<usize, usize>::with_max_entries(3);
+	for i in 0..3 {
+		assert_eq!(map.insert_bounded(i, i).unwrap().unwrap(), &i);
+	}
+
+	// Collisions still work as usual when full.
+	assert_eq!(map.insert_bounded(1, 10).unwrap().unwrap_err(), (1, 10));
+
+	match map.insert_bounded(3, 3) {
+		Err(CapacityExceeded { key: 3, value: 3 }) => (),
+		_ => panic!("Expected `CapacityExceeded`."),
+	}
+	assert_eq!(map.get(&3), None);
+}
+
+#[test]
+fn max_entries_emplace() {
+	let map = PineMap::<usize, String>::with_max_entries(1);
+	match map.try_emplace_with_bounded(0, |key, slot| {
+		Ok::<_, Infallible>(slot.write(key.to_string()))
+	}) {
+		Ok(Ok((emplaced, Ok(())))) => assert_eq!(emplaced, "0"),
+		_ => panic!("Expected a new entry."),
+	}
+
+	match map.try_emplace_with_bounded(1, |_, _| -> Result<_, Infallible> {
+		unreachable!("The factory isn't called when full.")
+	}) {
+		Err(CapacityExceeded { key: 1, .. }) => (),
+		_ => panic!("Expected `CapacityExceeded`."),
+	}
+	assert_eq!(map.len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "max_entries")]
+fn max_entries_panic() {
+	let map = PineMap::<usize, usize>::with_max_entries(1);
+	map.insert(0, 0).unwrap();
+	let _ = map.insert(1, 1);
+}
+
+#[test]
+#[should_panic(expected = "max_entries")]
+fn max_entries_extend_panic() {
+	let mut map = PineMap::<usize, usize>::with_max_entries(2);
+	map.extend((0..3).map(|i| (i, i)));
+}
+
+#[test]
+fn max_entries_clone_from() {
+	let source = PineMap::<usize, usize>::with_max_entries(3);
+	for i in 0..3 {
+		source.insert(i, i).unwrap();
+	}
+	let mut target = PineMap::with_max_entries(1);
+	target.clone_from(&source);
+	assert_eq!(target.len(), 3);
+	assert!(matches!(
+		target.insert_bounded(3, 3),
+		Err(CapacityExceeded { .. })
+	));
+}
+
+#[test]
+fn stable_address() {
+	let map = PineMap::<usize, usize>::new();
+	map.insert(0, 0).unwrap();
+	let address = map.stable_address(&0).unwrap();
+	assert_eq!(address, map.get(&0).unwrap() as *const usize as usize);
+
+	for i in 1..1000 {
+		map.insert(i, i).unwrap();
+	}
+	assert_eq!(map.stable_address(&0), Some(address));
+	assert_eq!(map.stable_address(&1000), None);
+
+	let mut map = map.pin();
+	for i in 1..1000 {
+		map.drop_entry(&i);
+	}
+	assert_eq!(map.stable_address(&0), Some(address));
+}
+
+#[test]
+fn fill_clear_cycles() {
+	let mut map = PineMap::<usize, String>::with_capacity(8);
+	for cycle in 0..5 {
+		for i in 0..20 {
+			map.insert(i, format!("{}/{}", cycle, i)).unwrap();
+		}
+		for i in (0..20).step_by(3) {
+			assert_eq!(map.remove_value(&i), Some(format!("{}/{}", cycle, i)));
+		}
+		for i in (0..20).step_by(3) {
+			map.insert_mut(i, format!("{}/{} again", cycle, i)).unwrap();
+		}
+		for i in (1..20).step_by(3) {
+			map.drop_entry(&i);
+		}
+
+		map.clear();
+		assert_eq!(map.get(&0), None);
+
+		// Any holes surviving `clear` would now overlap fresh allocations.
+		for i in 0..20 {
+			map.insert(i, i.to_string()).unwrap();
+		}
+		for i in 0..20 {
+			assert_eq!(map.get(&i).unwrap(), &i.to_string());
+		}
+		map.clear();
+	}
+}
+
+#[test]
+fn get_disjoint_pinned_mut() {
+	use std::{marker::PhantomPinned, pin::Pin, ptr};
+
+	struct Node {
+		partner: *const Node,
+		_pinned: PhantomPinned,
+	}
+	impl Node {
+		fn link(self: Pin<&mut Self>, partner: Pin<&Node>) {
+			unsafe { self.get_unchecked_mut() }.partner = &*partner;
+		}
+	}
+
+	let mut map = PineMap::new().pin();
+	for i in 0..3 {
+		map.insert(
+			i,
+			Node {
+				partner: ptr::null(),
+				_pinned: PhantomPinned,
+			},
+		)
+		.ok()
+		.unwrap();
+	}
+
+	match map.get_disjoint_pinned_mut([&0, &2, &5]) {
+		[Some(mut a), Some(mut b), None] => {
+			a.as_mut().link(b.as_ref());
+			b.as_mut().link(a.as_ref());
+		}
+		_ => unreachable!(),
+	}
+
+	let a = map.get(&0).unwrap();
+	let b = map.get(&2).unwrap();
+	assert!(ptr::eq(a.partner, &*b));
+	assert!(ptr::eq(b.partner, &*a));
+	assert!(map.get(&1).unwrap().partner.is_null());
+}
+
+#[test]
+#[should_panic(expected = "overlapping")]
+fn get_disjoint_mut_overlapping() {
+	let mut map = PineMap::<usize, usize>::new();
+	map.insert(1, 1).unwrap();
+	let _ = map.get_disjoint_mut([&1, &2, &1]);
+}
+
+#[test]
+#[should_panic(expected = "overlapping")]
+fn get_disjoint_mut_inconsistent_ord() {
+	use std::cmp::Ordering;
+
+	/// Tagged keys only compare by tag among each other, so two of them can match the same untagged key.
+	struct Key {
+		id: u8,
+		tag: u8,
+	}
+	impl PartialEq for Key {
+		fn eq(&self, other: &Self) -> bool {
+			self.cmp(other) == Ordering::Equal
+		}
+	}
+	impl Eq for Key {}
+	impl PartialOrd for Key {
+		fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+			Some(self.cmp(other))
+		}
+	}
+	impl Ord for Key {
+		fn cmp(&self, other: &Self) -> Ordering {
+			if self.tag != 0 && other.tag != 0 {
+				self.tag.cmp(&other.tag)
+			} else {
+				self.id.cmp(&other.id)
+			}
+		}
+	}
+
+	let mut map = PineMap::<Key, usize>::new();
+	map.insert(Key { id: 1, tag: 0 }, 1).ok().unwrap();
+	let _ = map.get_disjoint_mut([&Key { id: 1, tag: 1 }, &Key { id: 1, tag: 2 }]);
+}
+
+#[test]
+fn extend_from_slice() {
+	let mut map = PineMap::<usize, usize>::new();
+	map.insert(1, 10).unwrap();
+	map.extend_from_slice(&[(0, 0), (1, 1), (2, 2), (2, 20), (3, 3)]);
+
+	assert_eq!(map.get(&0), Some(&0));
+	assert_eq!(map.get(&2).copied(), map.index_of(&2));
+	assert_eq!(map.get(&1), Some(&10));
+	assert_eq!(map.get(&2), Some(&2));
+	assert_eq!(map.get(&3), Some(&3));
+}
+
+#[test]
+fn get_exact() {
+	let mut map = PineMap::<String, usize>::new();
+	for (i, key) in ["a", "b", "c"].iter().enumerate() {
+		map.insert(key.to_string(), i).unwrap();
+	}
+
+	for key in ["a", "b", "c", "d"].iter() {
+		let owned = key.to_string();
+		assert_eq!(map.get_exact(&owned), map.get(*key));
+		assert_eq!(map.get_exact_mut(&owned).copied(), map.get(*key).copied());
+	}
+
+	let map = map.pin();
+	assert_eq!(map.get_exact(&"b".to_string()), map.get("b"));
+}
+
+#[test]
+fn failed_factory_keeps_state() {
+	let mut map = PineMap::<usize, usize>::new();
+	map.insert(0, 0).unwrap();
+	let address = map.stable_address(&0).unwrap();
+	map.drop_entry(&0);
+
+	assert_eq!(
+		map.try_insert_with_mut(1, |_| Err("nope")).err(),
+		Some("nope")
+	);
+	assert_eq!(map.get(&1), None);
+
+	// The hole popped for the failed attempt was returned and is reused now.
+	assert!(map.insert_with(2, |key| *key).is_ok());
+	assert_eq!(map.stable_address(&2), Some(address));
+}
+
+#[test]
+fn try_values_as_slice() {
+	let map = PineMap::<usize, usize>::with_capacity(10);
+	assert_eq!(map.try_values_as_slice(), Some(&[][..]));
+	for i in 0..10 {
+		map.insert(i, i * 2).unwrap();
+	}
+
+	let mut values = map.try_values_as_slice().unwrap().to_vec();
+	values.sort_unstable();
+	assert_eq!(values, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+
+	let mut map = map;
+	map.drop_entry(&5);
+	assert_eq!(map.try_values_as_slice(), None);
+}
+
+#[test]
+fn remove_boxed() {
+	use std::any::Any;
+
+	let mut map = PressedPineMap::<u32, dyn Any>::new();
+	assert!(map.emplace_with(1, |_, slot| slot.write(1_u8)).is_ok());
+	assert!(map
+		.emplace_with(2, |_, slot| slot.write("two".to_string()))
+		.is_ok());
+	assert!(map.emplace_with(3, |_, slot| slot.write(())).is_ok());
+
+	let (key, value) = map.remove_boxed(&2).unwrap();
+	assert_eq!(key, 2);
+	assert_eq!(*value.downcast::<String>().unwrap(), "two");
+
+	let (_, value) = map.remove_boxed(&3).unwrap();
+	assert!(value.downcast::<()>().is_ok());
+
+	assert!(map.remove_boxed(&2).is_none());
+	assert_eq!(map.get(&1).unwrap().downcast_ref::<u8>(), Some(&1));
+}
+
+#[test]
+fn entry_retry() {
+	let map = PineMap::<usize, String>::new();
+	map.insert(1, "one".to_string()).unwrap();
+
+	assert_eq!(
+		map.entry(1)
+			.or_try_insert_with::<_, ()>(|_| unreachable!())
+			.unwrap(),
+		"one"
+	);
+
+	let vacant = match map.entry(2).or_try_insert_with(|_| Err("transient")) {
+		Err((vacant, "transient")) => vacant,
+		_ => unreachable!(),
+	};
+	assert_eq!(vacant.key(), &2);
+	let value = match vacant.try_insert_with::<_, ()>(|key| Ok(key.to_string())) {
+		Ok(value) => value,
+		Err(_) => unreachable!(),
+	};
+	assert_eq!(value, "2");
+	assert_eq!(map.get(&2), Some(value));
+
+	match map.entry(3) {
+		Entry::Vacant(vacant) => assert_eq!(vacant.into_key(), 3),
+		Entry::Occupied(_) => unreachable!(),
+	}
+	assert_eq!(map.entry(3).or_insert_with(|| "three".to_string()), "three");
+	assert_eq!(map.entry(3).or_insert("drei".to_string()), "three");
+}
+
+#[test]
+fn index_of_get_by_index() {
+	let map = PineMap::<String, usize>::new();
+	for i in [5, 3, 8, 1, 9].iter() {
+		map.insert(i.to_string(), *i).unwrap();
+	}
+
+	for i in 0..5 {
+		let (key, value) = map.get_by_index(i).unwrap();
+		assert_eq!(map.index_of(&key), Some(i));
+		assert_eq!(value.to_string(), key);
+	}
+	assert_eq!(map.get_by_index(5), None);
+	assert_eq!(map.index_of("8"), Some(3));
+	assert_eq!(map.index_of("4"), None);
+
+	let map = map.pin();
+	assert_eq!(*map.get_by_index(0).unwrap().1, 1);
+	assert_eq!(map.index_of("9"), Some(4));
+}
+
+#[test]
+fn compact_into() {
+	let mut map = PineMap::<usize, String>::new();
+	for i in 0..100 {
+		map.insert(i, i.to_string()).unwrap();
+	}
+	for i in (0..100).filter(|i| i % 3 != 0) {
+		map.drop_entry(&i);
+	}
+	assert_eq!(map.hole_count(), 66);
+
+	let compacted = map.compact_into();
+	assert_eq!(compacted.hole_count(), 0);
+	assert!(compacted.try_values_as_slice().is_some());
+	for i in 0..100 {
+		assert_eq!(compacted.get(&i), map.get(&i));
+	}
+	assert_eq!(map.hole_count(), 66);
+	assert_eq!(map.get(&99).unwrap(), "99");
+}
+
+#[test]
+fn len_relaxed() {
+	let mut map = PineMap::new();
+	assert!(map.is_empty());
+	for i in 0..50 {
+		map.insert(i, i).unwrap();
+	}
+	map.write().insert(50, 50).unwrap();
+	if let Entry::Vacant(entry) = map.entry(51) {
+		entry.insert(51);
+	}
+	for i in (0..50).step_by(2) {
+		map.drop_entry(&i);
+	}
+	assert_eq!(map.len(), 27);
+	assert_eq!(map.len_relaxed(), map.len());
+
+	map.clear();
+	assert_eq!(map.len_relaxed(), 0);
+
+	let mut pressed = PressedPineMap::<_, str>::new();
+	pressed.emplace(1, "1".to_string()).unwrap();
+	pressed.emplace(2, "2".to_string()).unwrap();
+	pressed.remove_boxed(&1).unwrap();
+	assert_eq!(pressed.len(), 1);
+	assert_eq!(pressed.len_relaxed(), pressed.len());
+}
+
+#[test]
+fn try_emplace_opt() {
+	let map = PineMap::<usize, usize>::new();
+	for i in 0..10 {
+		let inserted = map
+			.try_emplace_opt::<_, Infallible>(i, |key, slot| {
+				(key % 2 == 0).then(move || Ok(slot.write(key * 10)))
+			})
+			.unwrap()
+			.0;
+		assert_eq!(inserted.is_some(), i % 2 == 0);
+	}
+	assert_eq!(map.len(), 5);
+	for i in 0..10 {
+		assert_eq!(map.get(&i), (i % 2 == 0).then(|| i * 10).as_ref());
+	}
+	// Each declined slot was reused for the next even key, except for the last one.
+	assert_eq!(map.hole_count(), 1);
+}
+
+#[test]
+fn drain_range() {
+	let mut map = PineMap::new();
+	for i in 0..10 {
+		map.insert(i, i.to_string()).unwrap();
+	}
+
+	let drained = map.drain_range(3..7).collect::<Vec<_>>();
+	assert_eq!(
+		drained,
+		(3..7).map(|i| (i, i.to_string())).collect::<Vec<_>>()
+	);
+	assert_eq!(map.hole_count(), 4);
+	assert_eq!(map.len_relaxed(), 6);
+	for i in 0..10 {
+		assert_eq!(map.get(&i).is_some(), !(3..7).contains(&i));
+	}
+
+	// Dropping the iterator early still removes the rest of the range.
+	assert_eq!(map.drain_range(..=7).next(), Some((0, "0".to_string())));
+	assert_eq!(map.len(), 2);
+	assert_eq!(map.get(&8).unwrap(), "8");
+	assert_eq!(map.get(&9).unwrap(), "9");
+}
+
+#[test]
+fn get_expect() {
+	let map = PineMap::new();
+	map.insert(1, "one").unwrap();
+	assert_eq!(*map.get_expect(&1, |key| format!("missing {}", key)), "one");
+
+	let map = map.pin();
+	assert_eq!(*map.get_expect(&1, |_| unreachable!()), "one");
+}
+
+#[test]
+#[should_panic(expected = "no entry for 2")]
+fn get_expect_missing() {
+	let map = PineMap::new();
+	map.insert(1, "one").unwrap();
+	map.get_expect(&2, |key| format!("no entry for {}", key));
+}
+
+#[test]
+fn push_slice() {
+	let map = PressedPineMap::<_, [u8]>::new();
+	assert_eq!(map.push_slice(1, b"a").unwrap(), b"a");
+	assert_eq!(map.push_slice(2, b"").unwrap(), b"");
+	assert_eq!(map.push_slice(3, b"abcdefgh").unwrap(), b"abcdefgh");
+	assert_eq!(map.push_slice(1, b"bc").unwrap_err(), (1, b"bc".to_vec()));
+
+	assert_eq!(map.get(&1), Some(&b"a"[..]));
+	assert_eq!(map.get(&2), Some(&b""[..]));
+	assert_eq!(map.get(&3), Some(&b"abcdefgh"[..]));
+}
+
+#[test]
+fn fold_values() {
+	let numbers = PineMap::new();
+	for i in 1..=10 {
+		numbers.insert(i, i).unwrap();
+	}
+	assert_eq!(numbers.fold_values(0, |sum, value| sum + value), 55);
+
+	let strings = PineMap::new();
+	strings.insert(2, "b".to_string()).unwrap();
+	strings.insert(1, "a".to_string()).unwrap();
+	strings.insert(3, "c".to_string()).unwrap();
+	assert_eq!(
+		strings.fold_values(String::new(), |acc, value| acc + value),
+		"abc"
+	);
+
+	let strings = strings.pin();
+	assert_eq!(
+		strings.fold_values(String::new(), |acc, value| acc + &*value),
+		"abc"
+	);
+}
+
+#[test]
+fn remove_key_into() {
+	let mut map = PineMap::new();
+	let key = "a key with capacity".to_string();
+	let address = key.as_ptr();
+	map.insert(key, 1).unwrap();
+
+	let mut reuse = None;
+	assert!(!map.remove_key_into("missing", &mut reuse));
+	assert!(reuse.is_none());
+	assert!(map.remove_key_into("a key with capacity", &mut reuse));
+	assert!(map.is_empty());
+
+	let mut key = reuse.unwrap();
+	assert_eq!(key.as_ptr(), address);
+	key.clear();
+	key.push_str("reused");
+	map.insert(key, 2).unwrap();
+	assert_eq!(map.get("reused"), Some(&2));
+}
+
+#[test]
+fn iter_downcast() {
+	use std::any::Any;
+
+	let map = PressedPineMap::<_, dyn Any>::new();
+	for i in 0..10_u8 {
+		if i % 3 == 0 {
+			map.emplace_with(i, |_, slot| slot.write(i.to_string()))
+				.ok()
+				.unwrap();
+		} else {
+			map.emplace_with(i, |_, slot| slot.write(i)).ok().unwrap();
+		}
+	}
+
+	let strings = map
+		.iter_downcast::<String>()
+		.map(|(key, value)| (key, value.as_str()))
+		.collect::<Vec<_>>();
+	assert_eq!(strings, [(0, "0"), (3, "3"), (6, "6"), (9, "9")]);
+	assert_eq!(map.iter_downcast::<u8>().count(), 6);
+	assert!(map
+		.iter_downcast::<u8>()
+		.all(|(key, &value)| key == value && key % 3 != 0));
+	assert_eq!(map.iter_downcast::<()>().next(), None);
+}
+
+#[test]
+fn new_with_arena() {
+	use std::sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	};
+
+	let arena = bumpalo::Bump::with_capacity(1024);
+	arena.alloc(0_u8);
+
+	let mut map = PineMap::new_with_arena(arena);
+	assert_eq!(map.allocated_bytes(), 0);
+	let growths = Arc::new(AtomicUsize::new(0));
+	map.set_growth_observer(Box::new({
+		let growths = Arc::clone(&growths);
+		move |_| {
+			growths.fetch_add(1, Ordering::Relaxed);
+		}
+	}));
+	for i in 0..100_u64 {
+		map.insert(i, i).unwrap();
+	}
+	assert_eq!(map.allocated_bytes(), 800);
+	// All values were placed in the arena's existing chunk.
+	assert_eq!(growths.load(Ordering::Relaxed), 0);
+	assert_eq!(map.try_values_as_slice().map(<[_]>::len), Some(100));
+
+	let pressed = PressedPineMap::<_, [u8]>::new_with_arena(bumpalo::Bump::with_capacity(1024));
+	pressed.push_slice(1, &[0; 512]).unwrap();
+	assert_eq!(pressed.allocated_bytes(), 512);
+}
+
+#[test]
+fn pinned_key_queries() {
+	struct Large {
+		_data: [u8; 1 << 16],
+		_pinned: PhantomPinned,
+	}
+
+	let map = PineMap::new().pin();
+	for i in 0..10 {
+		map.emplace_with_unpinned(i, |_, slot| {
+			slot.write(Large {
+				_data: [0; 1 << 16],
+				_pinned: PhantomPinned,
+			})
+		})
+		.ok()
+		.unwrap();
+	}
+	assert!(map.contains_key(&3));
+	assert!(!map.contains_key(&10));
+	assert_eq!(map.len(), 10);
+	assert_eq!(map.count_range(2..5), 3);
+	assert_eq!(map.count_range(..), 10);
+	assert_eq!(map.count_range(8..), 2);
+}
+
+#[test]
+fn insert_with_index() {
+	let map = PineMap::new();
+	for i in (0..10).step_by(2) {
+		assert_eq!(map.insert_with_index(i, |_| i).ok().unwrap().0, i / 2);
+	}
+
+	let (index, value) = map.insert_with_index(5, |key| key * 10).ok().unwrap();
+	assert_eq!((index, *value), (3, 50));
+	assert_eq!(map.index_of(&5), Some(3));
+
+	let (index, value) = map.insert_with_index(4, |_| unreachable!()).0;
+	assert_eq!((index, *value), (2, 4));
+}
+
+#[test]
+fn take_all() {
+	let mut map = PineMap::new();
+	for i in (0..5).rev() {
+		map.insert(i, i.to_string()).unwrap();
+	}
+	map.drop_entry(&2);
+
+	let pairs = map.take_all();
+	assert!(map.is_empty());
+	assert_eq!(map.len_relaxed(), 0);
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(
+		pairs,
+		[0, 1, 3, 4]
+			.iter()
+			.map(|&i| (i, i.to_string()))
+			.collect::<Vec<_>>()
+	);
+
+	map.insert(5, "5".to_string()).unwrap();
+	assert_eq!(map.get(&5).unwrap(), "5");
+	assert_eq!(pairs[3].1, "4");
+}
+
+#[test]
+fn contiguous_batch() {
+	let mut map = PineMap::with_capacity(20);
+	for i in 0..10_u64 {
+		map.insert(i, i).unwrap();
+	}
+	map.drop_entry(&3);
+	map.drop_entry(&6);
+	assert_eq!(map.hole_count(), 2);
+
+	map.contiguous_batch(|batch| {
+		for i in 10..15 {
+			batch.insert(i, i).unwrap();
+		}
+	});
+	assert_eq!(map.hole_count(), 2);
+
+	let mut addresses = (10..15)
+		.map(|i| map.stable_address(&i).unwrap())
+		.collect::<Vec<_>>();
+	addresses.sort_unstable();
+	assert!(addresses
+		.windows(2)
+		.all(|pair| pair[1] - pair[0] == std::mem::size_of::<u64>()));
+
+	// Holes are reused again afterwards.
+	map.insert(15, 15).unwrap();
+	assert_eq!(map.hole_count(), 1);
+}
+
+#[test]
+fn from_iterator_boxed() {
+	use std::any::Any;
+
+	let entries: Vec<(u32, Box<dyn Any>)> = vec![
+		(1, Box::new(1_u8)),
+		(2, Box::new("two".to_string())),
+		(3, Box::new(())),
+		(1, Box::new("duplicate")),
+	];
+	let mut map = entries.into_iter().collect::<PressedPineMap<_, _>>();
+	assert_eq!(map.len(), 3);
+	assert_eq!(map.get(&1).unwrap().downcast_ref::<u8>(), Some(&1));
+	assert_eq!(
+		map.get(&2).unwrap().downcast_ref::<String>().unwrap(),
+		"two"
+	);
+	assert!(map.get(&3).unwrap().is::<()>());
+
+	let (_, two) = map.remove_boxed(&2).unwrap();
+	assert_eq!(*two.downcast::<String>().unwrap(), "two");
+}
+
+#[test]
+fn get_key_value_mut() {
+	let mut map = PineMap::new();
+	map.insert("four".to_string(), 0).unwrap();
+	map.insert("three".to_string(), 0).unwrap();
+
+	let (key, value) = map.get_key_value_mut("four").unwrap();
+	*value = key.len();
+	assert_eq!(map.get("four"), Some(&4));
+	assert!(map.get_key_value_mut("five").is_none());
+
+	let mut map = map.pin();
+	let (key, mut value) = map.get_key_value_mut("three").unwrap();
+	*value = key.len();
+	assert_eq!(*map.get("three").unwrap(), 5);
+}
+
+#[test]
+fn borrow_unsized() {
+	use std::fmt::Display;
+
+	struct Concrete(u8);
+	impl Display for Concrete {
+		fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+			write!(f, "Concrete({})", self.0)
+		}
+	}
+	pinus::borrow_unsized!(Concrete => dyn Display);
+
+	let map = PressedPineMap::<_, dyn Display>::new();
+	map.emplace(1, Concrete(1)).ok().unwrap();
+	assert_eq!(map.emplace(1, Concrete(2)).err().unwrap().1 .0, 2);
+	assert_eq!(map.get(&1).unwrap().to_string(), "Concrete(1)");
+}
+
+#[test]
+fn value_ptrs() {
+	let map = PineMap::new();
+	for i in (0..10).rev() {
+		map.insert(i, i * 2).unwrap();
+	}
+	let pointers = map.value_ptrs();
+	assert_eq!(pointers.len(), 10);
+	for (i, pointer) in pointers.into_iter().enumerate() {
+		assert_eq!(
+			pointer.as_ptr() as *const _,
+			map.get(&i).unwrap() as *const _
+		);
+		assert_eq!(unsafe { *pointer.as_ptr() }, i * 2);
+	}
+}
+
+#[test]
+fn try_insert_with_growth() {
+	let map = PineMap::with_capacity(4);
+	let grown = (0..1000_u64)
+		.map(|i| {
+			map.try_insert_with_growth::<_, Infallible>(i, |&key| Ok(key))
+				.unwrap()
+				.ok()
+				.unwrap()
+				.1
+		})
+		.collect::<Vec<_>>();
+
+	// The first chunk has room for (at least) four values, and chunks grow geometrically.
+	let first_growth = grown.iter().position(|&grew| grew).unwrap();
+	assert!(first_growth >= 4);
+	let growths = grown.iter().filter(|&&grew| grew).count();
+	assert!((1..10).contains(&growths));
+
+	// Existing entries don't cause growth.
+	let (value, grew) = map
+		.try_insert_with_growth::<_, Infallible>(0, |_| unreachable!())
+		.unwrap()
+		.0;
+	assert_eq!((*value, grew), (0, false));
+}
+
+#[test]
+fn values_vec() {
+	let map = PineMap::new();
+	for i in (0..5).rev() {
+		map.insert(i, i.to_string()).unwrap();
+	}
+	let values = map.values_vec();
+	// Insertions don't invalidate the collected references.
+	map.insert(5, "5".to_string()).unwrap();
+	assert_eq!(values, ["0", "1", "2", "3", "4"]);
+
+	let map = map.pin();
+	let values = map.values_vec();
+	assert_eq!(values.len(), 6);
+	assert!(values
+		.iter()
+		.enumerate()
+		.all(|(i, value)| **value == i.to_string()));
+}
+
+#[test]
+fn pinned_entry() {
+	let mut map = PineMap::new().pin();
+	for word in ["a", "b", "a", "c", "a", "b"] {
+		*PineMap::pinned_entry(&mut map, word).or_insert(0) += 1;
+	}
+	assert_eq!(*map.get("a").unwrap(), 3);
+	assert_eq!(*map.get("b").unwrap(), 2);
+	assert_eq!(*map.get("c").unwrap(), 1);
+
+	let entry = PineMap::pinned_entry(&mut map, "d");
+	assert_eq!(*entry.key(), "d");
+	assert_eq!(*entry.or_insert_with(|| 10), 10);
+	assert_eq!(map.as_unpinned().len_relaxed(), 4);
+}
+
+#[test]
+fn remove_then_insert_reuses_slot() {
+	let mut map = PineMap::new();
+	map.insert(1, "one".to_string()).unwrap();
+	map.insert(2, "two".to_string()).unwrap();
+	let address = map.stable_address(&1).unwrap();
+
+	assert_eq!(map.remove_pair(&1), Some((1, "one".to_string())));
+	map.insert(3, "three".to_string()).unwrap();
+	assert_eq!(map.stable_address(&3), Some(address));
+	assert_eq!(map.get(&2).unwrap(), "two");
+	assert_eq!(map.get(&3).unwrap(), "three");
+}
+
+#[test]
+fn shrink_to() {
+	let mut map = PineMap::new();
+	for i in 0..1000_u64 {
+		map.insert(i, i).unwrap();
+	}
+	for i in 10..1000 {
+		map.drop_entry(&i);
+	}
+	assert_eq!(map.allocated_bytes(), 8000);
+
+	map.shrink_to(50);
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(map.allocated_bytes(), 80);
+	assert_eq!(map.try_values_as_slice().unwrap().len(), 10);
+	for i in 0..10 {
+		assert_eq!(map.get(&i), Some(&i));
+	}
+
+	// There's room for (about) 50 entries without growing the arena.
+	for i in 10..50 {
+		let (_, grew) = map
+			.try_insert_with_growth::<_, Infallible>(i, |&key| Ok(key))
+			.unwrap()
+			.0;
+		assert!(!grew);
+	}
+}
+
+#[test]
+fn pinned_unsized_self_referential() {
+	trait SelfCheck {
+		fn check(self: Pin<&Self>) -> bool;
+	}
+
+	struct SelfReferential {
+		value: usize,
+		this: *const SelfReferential,
+		_pinned: PhantomPinned,
+	}
+	impl SelfCheck for SelfReferential {
+		fn check(self: Pin<&Self>) -> bool {
+			std::ptr::eq(self.this, &*self) && unsafe { &*self.this }.value == self.value
+		}
+	}
+
+	let map = PressedPineMap::<_, dyn SelfCheck>::new().pin();
+	for i in 0..1000 {
+		map.emplace_with_unpinned(i, |_, slot| {
+			let value = slot.write(SelfReferential {
+				value: i,
+				this: std::ptr::null(),
+				_pinned: PhantomPinned,
+			});
+			value.this = value;
+			value
+		})
+		.ok()
+		.unwrap();
+	}
+
+	// Neither the b-tree's rebalancing nor arena growth moved any value.
+	for i in 0..1000 {
+		assert!(map.get(&i).unwrap().check());
+	}
+}
+
+#[test]
+fn remove_range() {
+	let mut map = PineMap::new();
+	for i in 0..20_u64 {
+		map.insert(i, i).unwrap();
+	}
+	assert_eq!(map.allocated_bytes(), 160);
+
+	// The most recently inserted entries' slots are released right away.
+	assert_eq!(map.remove_range(15..), 5);
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(map.allocated_bytes(), 120);
+
+	// Others are reused.
+	assert_eq!(map.remove_range(..5), 5);
+	assert_eq!(map.hole_count(), 5);
+	assert_eq!(map.allocated_bytes(), 120);
+	for i in 100..105 {
+		map.insert(i, i).unwrap();
+	}
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(map.allocated_bytes(), 120);
+
+	// Together with earlier holes, that's also released as soon as it's at the top.
+	assert_eq!(map.remove_range(5..10), 5);
+	assert_eq!(map.hole_count(), 5);
+	assert_eq!(map.remove_range(10..15), 5);
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(map.allocated_bytes(), 40);
+
+	// Removing everything reclaims the arena.
+	assert_eq!(map.remove_range(..), 5);
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(map.allocated_bytes(), 0);
+}
+
+#[test]
+fn insert_boxed() {
+	let map = PineMap::new();
+	let mut key = Box::new([0_u8; 4096]);
+	key[4095] = 1;
+	assert_eq!(*map.insert_boxed(key.clone(), 1).unwrap(), 1);
+
+	let (existing, result) = map.insert_boxed(key.clone(), 2);
+	assert_eq!(*existing, 1);
+	let (returned_key, value) = result.unwrap_err();
+	assert_eq!(returned_key, key);
+	assert_eq!(value, 2);
+
+	assert_eq!(*map.insert(*Box::new([0_u8; 4096]), 3).unwrap(), 3);
+	assert_eq!(map.get(&*key), Some(&1));
+	assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn get_copied() {
+	let mut map = PineMap::new();
+	map.insert("answer", 42).unwrap();
+	let answer = map.get_copied("answer");
+	// No borrow of `map` remains.
+	map.clear();
+	assert_eq!(answer, Some(42));
+	assert_eq!(map.get_copied("answer"), None);
+
+	let map = map.pin();
+	map.insert("question", 6 * 9).unwrap();
+	assert_eq!(map.get_copied("question"), Some(54));
+}
+
+#[test]
+fn retain_update() {
+	struct Counter {
+		count: usize,
+		_pinned: PhantomPinned,
+	}
+
+	let mut map = PineMap::new().pin();
+	for i in 0..10 {
+		map.insert(
+			i,
+			Counter {
+				count: i,
+				_pinned: PhantomPinned,
+			},
+		)
+		.ok()
+		.unwrap();
+	}
+
+	let removed = map.retain_update(|key, counter| {
+		unsafe { counter.get_unchecked_mut() }.count += 10;
+		key % 2 == 0
+	});
+	assert_eq!(removed, 5);
+	assert_eq!(map.len(), 5);
+	for i in 0..10 {
+		assert_eq!(
+			map.get(&i).map(|counter| counter.count),
+			(i % 2 == 0).then(|| i + 10)
+		);
+	}
+	assert_eq!(map.as_unpinned().hole_count(), 5);
+}
+
+#[test]
+fn try_insert_with2() {
+	use pinus::InsertError;
+
+	type Factory = fn(&u8) -> Result<u8, String>;
+
+	fn insert_all(
+		map: &PineMap<u8, u8>,
+		keys: &[u8],
+	) -> Result<(), InsertError<u8, Factory, String>> {
+		for &key in keys {
+			map.try_insert_with2(
+				key,
+				(|&key| {
+					if key < 100 {
+						Ok(key * 2)
+					} else {
+						Err(format!("{} is too large", key))
+					}
+				}) as Factory,
+			)?;
+		}
+		Ok(())
+	}
+
+	let map = PineMap::new();
+	assert!(insert_all(&map, &[1, 2, 3]).is_ok());
+	assert_eq!(map.get(&3), Some(&6));
+
+	match insert_all(&map, &[4, 2]).unwrap_err() {
+		InsertError::Occupied(key, _) => assert_eq!(key, 2),
+		InsertError::Factory(_) => unreachable!(),
+	}
+	match insert_all(&map, &[5, 100]).unwrap_err() {
+		InsertError::Factory(error) => assert_eq!(error, "100 is too large"),
+		InsertError::Occupied(..) => unreachable!(),
+	}
+	assert_eq!(map.len(), 5);
+}
+
+#[test]
+fn shared_borrow_interning() {
+	// A shared borrow already rules out removal, so its value references live as long as it does.
+	fn intern<'a>(interner: &'a PineMap<String, String>, text: &str) -> &'a str {
+		interner
+			.insert_with(text.to_string(), |text| text.to_uppercase())
+			.0
+	}
+
+	let map = PineMap::new();
+	let interner = &map;
+	let a = intern(interner, "a");
+	let b = intern(interner, "b");
+	let a_again = intern(interner, "a");
+	let c = interner.insert("c".to_string(), "C".to_string()).unwrap();
+
+	assert!(std::ptr::eq(a, a_again));
+	assert_eq!([a, b, c], ["A", "B", "C"]);
+	assert_eq!(interner.get("b").map(String::as_str), Some(b));
+	assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn reserve_holes() {
+	let mut map = PineMap::new();
+	map.insert(0_u64, 0_u64).unwrap();
+	map.insert(1, 1).unwrap();
+	map.drop_entry(&1);
+
+	map.reserve_holes(10);
+	assert_eq!(map.hole_count(), 10);
+	let allocated = map.allocated_bytes();
+	assert_eq!(allocated, 8 * 11);
+
+	for i in 1..=10 {
+		map.insert(i, i).unwrap();
+	}
+	assert_eq!(map.hole_count(), 0);
+	assert_eq!(map.allocated_bytes(), allocated);
+
+	map.reserve_holes(0);
+	assert_eq!(map.hole_count(), 0);
+}
+
+#[test]
+fn debug_memory() {
+	let mut map = PineMap::new();
+	map.insert(1, "a").unwrap();
+	map.insert(2, "b").unwrap();
+	map.drop_entry(&2);
+
+	assert_eq!(format!("{:?}", map), r#"{1: "a"}"#);
+
+	let alternate = format!("{:#?}", map);
+	assert!(alternate.contains("memory: Memory {"));
+	assert!(alternate.contains("live: 1,"));
+	assert!(alternate.contains("holes: 1,"));
+}
+
+#[test]
+fn contains_all_any() {
+	let map = PineMap::new();
+	for key in ["a", "b", "c"].iter() {
+		map.insert(key.to_string(), ()).unwrap();
+	}
+
+	assert!(map.contains_all(["a", "b", "c"].iter().copied()));
+	assert!(map.contains_any(["a", "b", "c"].iter().copied()));
+
+	assert!(!map.contains_all(["a", "x"].iter().copied()));
+	assert!(map.contains_any(["x", "a"].iter().copied()));
+
+	assert!(!map.contains_all(["x", "y"].iter().copied()));
+	assert!(!map.contains_any(["x", "y"].iter().copied()));
+
+	assert!(map.contains_all(None::<&str>));
+	assert!(!map.contains_any(None::<&str>));
+}
+
+#[test]
+fn emplace_with_index() {
+	let map = PineMap::new();
+	for key in [5, 1, 3, 4, 0, 2].iter().copied() {
+		map.emplace_with_index(key, |_, index, slot| slot.write(index))
+			.ok()
+			.unwrap();
+	}
+	assert!(map
+		.emplace_with_index(3, |_, index, slot| slot.write(index))
+		.1
+		.is_err());
+
+	// Indices are taken at insertion time and shift as smaller keys are added later.
+	assert_eq!(map.get(&5), Some(&0));
+	assert_eq!(map.get(&3), Some(&1));
+	assert_eq!(map.get(&0), Some(&0));
+	assert_eq!(map.get(&2).copied(), map.index_of(&2));
+
+	let map = PressedPineMap::<_, usize>::new();
+	for key in [1, 3, 5].iter().copied() {
+		map.emplace_with_index(key, |_, index, slot| slot.write(index))
+			.ok()
+			.unwrap();
+	}
+	for key in [1, 3, 5].iter() {
+		assert_eq!(map.get(key).copied(), map.index_of(key));
+	}
+}
+
+#[test]
+fn eq_ignores_history() {
+	use std::{
+		collections::hash_map::DefaultHasher,
+		hash::{Hash, Hasher},
+	};
+
+	fn hash(map: &PineMap<u32, String>) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		map.hash(&mut hasher);
+		hasher.finish()
+	}
+
+	let clean = PineMap::new();
+	for i in 0..10 {
+		clean.insert(i, i.to_string()).unwrap();
+	}
+
+	let mut churned = PineMap::new();
+	for round in 0..5 {
+		for i in (0..100).rev() {
+			churned.insert(i, format!("{}-{}", round, i)).ok();
+		}
+		churned.clear();
+	}
+	for i in (0..50).rev() {
+		churned.insert(i, i.to_string()).unwrap();
+	}
+	for i in 10..50 {
+		churned.drop_entry(&i);
+	}
+	assert!(churned.hole_count() > 0);
+
+	assert!(clean == churned);
+	assert_eq!(hash(&clean), hash(&churned));
+
+	churned.drop_entry(&9);
+	churned.insert(9, "nine".to_string()).unwrap();
+	assert!(clean != churned);
+}
+
+#[test]
+fn try_for_each_mut() {
+	let mut map = PineMap::new();
+	for i in 0..5 {
+		map.insert(i, i).unwrap();
+	}
+
+	let result = map.try_for_each_mut(|key, value| {
+		if *key == 2 {
+			Err(*key)
+		} else {
+			*value += 10;
+			Ok(())
+		}
+	});
+	assert_eq!(result, Err(2));
+	assert_eq!(map.values_vec(), vec![&10, &11, &2, &3, &4]);
+
+	let mut map = map.pin();
+	map.try_for_each_mut(|_, value| {
+		*value.get_mut() += 1;
+		Ok::<_, Infallible>(())
+	})
+	.unwrap_or_else(|never| match never {});
+	assert_eq!(map.as_unpinned().values_vec(), vec![&11, &12, &3, &4, &5]);
+}
+
+#[test]
+fn cow_keys() {
+	use std::borrow::Cow;
+
+	let map = PineMap::<Cow<str>, _>::new();
+	map.insert(Cow::Borrowed("borrowed"), 1).unwrap();
+	map.insert(Cow::Owned("owned".to_string()), 2).unwrap();
+
+	assert_eq!(map.get("borrowed"), Some(&1));
+	assert_eq!(map.get("owned"), Some(&2));
+
+	let owned = "owned".to_string();
+	assert_eq!(map.get(owned.as_str()), Some(&2));
+	assert_eq!(map.get(&*owned), Some(&2));
+	assert_eq!(map.get(&Cow::Borrowed("borrowed")), Some(&1));
+}
+
+#[test]
+fn into_sorted_vec() {
+	let map = PineMap::new();
+	for i in [3, 1, 4, 0, 2].iter().copied() {
+		map.insert(i, i.to_string()).unwrap();
+	}
+
+	let entries = map.into_sorted_vec();
+	assert_eq!(
+		entries,
+		(0..5).map(|i| (i, i.to_string())).collect::<Vec<_>>()
+	);
+}
+
+#[test]
+fn upgradable_read() {
+	use std::{sync::Arc, thread, time::Duration};
+
+	let map = Arc::new(PineMap::new());
+	map.insert(0, "zero").unwrap();
+
+	let guard = map.upgradable_read();
+	let racer = thread::spawn({
+		let map = Arc::clone(&map);
+		move || map.insert(1, "racer").is_ok()
+	});
+	thread::sleep(Duration::from_millis(50));
+
+	assert_eq!(guard.get(&0), Some(&"zero"));
+	assert!(!guard.contains_key(&1));
+	let mut guard = guard.upgrade();
+	assert!(guard.insert(1, "guard").is_ok());
+	drop(guard);
+
+	assert!(!racer.join().unwrap());
+	assert_eq!(map.get(&1), Some(&"guard"));
+}
+
+#[test]
+fn over_aligned_values() {
+	use std::{any::Any, mem};
+
+	#[repr(align(128))]
+	#[derive(Debug)]
+	struct Aligned(u8);
+	pinus::borrow_unsized!(Aligned => dyn Any);
+
+	fn assert_aligned(value: &dyn Any) {
+		assert_eq!(value as *const dyn Any as *const u8 as usize % 128, 0);
+		assert_eq!(mem::align_of_val(value), 128);
+	}
+
+	let mut map = PressedPineMap::<_, dyn Any>::new();
+	// Misalign the arena's bump pointer first.
+	map.emplace_boxed(0, Box::new(0_u8)).ok().unwrap();
+	for i in 1..10 {
+		assert_aligned(map.emplace(i, Aligned(i)).unwrap());
+	}
+	assert_aligned(map.emplace_boxed(10, Box::new(Aligned(10))).ok().unwrap());
+
+	let (_, removed) = map.remove_boxed(&10).unwrap();
+	assert_aligned(&*removed);
+	assert_eq!(removed.downcast_ref::<Aligned>().unwrap().0, 10);
+}
+
+#[test]
+fn truncate() {
+	let mut map = PineMap::new();
+	for i in (0..10).rev() {
+		map.insert(i, i.to_string()).unwrap();
+	}
+
+	map.truncate(3);
+	assert_eq!(map.len(), 3);
+	assert_eq!(map.hole_count(), 7);
+	assert!(map.contains_all([0, 1, 2].iter()));
+
+	map.truncate(5);
+	assert_eq!(map.len(), 3);
+
+	let mut map = map.pin();
+	map.truncate(0);
+	assert!(map.is_empty());
+}
+
+#[test]
+fn range_mut() {
+	let mut map = PineMap::new();
+	for i in 0..10 {
+		map.insert(i, i).unwrap();
+	}
+
+	for (_, value) in map.range_mut(3..7) {
+		*value *= 2;
+	}
+	assert_eq!(
+		map.values_vec(),
+		vec![&0, &1, &2, &6, &8, &10, &12, &7, &8, &9]
+	);
+
+	let mut map = map.pin();
+	for (_, value) in map.range_pinned_mut(8..).rev() {
+		*value.get_mut() = 0;
+	}
+	assert_eq!(map.get(&7).map(|value| *value), Some(7));
+	assert_eq!(map.get(&8).map(|value| *value), Some(0));
+	assert_eq!(map.get(&9).map(|value| *value), Some(0));
+}
+
+#[test]
+fn get_or_insert_with_status() {
+	let map = PineMap::new();
+	let mut calls = 0;
+
+	let (value, inserted) = map.get_or_insert_with_status(1, |_| {
+		calls += 1;
+		"miss"
+	});
+	assert_eq!((*value, inserted), ("miss", true));
+
+	let (value, inserted) = map.get_or_insert_with_status(1, |_| {
+		calls += 1;
+		"hit"
+	});
+	assert_eq!((*value, inserted), ("miss", false));
+	assert_eq!(calls, 1);
+
+	let map = map.pin();
+	let (value, inserted) = map.get_or_insert_with_status(2, |_| "pinned");
+	assert_eq!((*value, inserted), ("pinned", true));
+}
+
+#[test]
+fn compare_and_swap() {
+	let mut map = PineMap::new();
+	map.insert("entry", (1, "first")).unwrap();
+
+	assert_eq!(
+		map.compare_and_swap("entry", &(1, "first"), (2, "second")),
+		Ok(&(2, "second"))
+	);
+	assert_eq!(
+		map.compare_and_swap("entry", &(1, "first"), (3, "stale")),
+		Err(Some(&(2, "second")))
+	);
+	assert_eq!(
+		map.compare_and_swap("missing", &(1, "first"), (3, "stale")),
+		Err(None)
+	);
+	assert_eq!(map.get("entry"), Some(&(2, "second")));
+	assert_eq!(map.hole_count(), 0);
+}
+
+#[test]
+fn keys_values_double_ended() {
+	let map = PineMap::new();
+	for i in [2, 0, 3, 1].iter().copied() {
+		map.insert(i, i * 10).unwrap();
+	}
+
+	assert_eq!(map.keys().rev().collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+	assert_eq!(map.keys().len(), map.len());
+	assert_eq!(
+		map.values().rev().copied().collect::<Vec<_>>(),
+		vec![30, 20, 10, 0]
+	);
+
+	let mut values = map.values();
+	assert_eq!(values.len(), 4);
+	assert_eq!((values.next(), values.next_back()), (Some(&0), Some(&30)));
+	assert_eq!(values.len(), 2);
+}
+
+#[test]
+fn try_with_capacity() {
+	assert_eq!(
+		PineMap::<u8, u64>::try_with_capacity(usize::MAX).err(),
+		Some(TryReserveError::CapacityOverflow)
+	);
+
+	let map = PineMap::<u8, u64>::try_with_capacity(100).ok().unwrap();
+	map.insert(0, 0).unwrap();
+	assert_eq!(map.len(), 1);
+}
+
+#[test]
+#[should_panic = "capacity overflow"]
+fn with_capacity_overflow() {
+	let _ = PineMap::<u8, u64>::with_capacity(usize::MAX);
+}
+
+#[test]
+fn extract_keys_if() {
+	let mut map = PineMap::new();
+	for i in 0..10 {
+		map.insert(i, i.to_string()).unwrap();
+	}
+
+	let extracted = map.extract_keys_if(|key| *key > 5).collect::<Vec<_>>();
+	assert_eq!(extracted, vec![6, 7, 8, 9]);
+	assert_eq!(map.keys().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+	assert_eq!(map.hole_count(), 4);
+
+	// Dropping the iterator early still removes the remaining matches.
+	let mut map = map.pin();
+	let mut extract = PineMap::pinned_extract_keys_if(&mut map, |key| key % 2 == 0);
+	assert_eq!(extract.next(), Some(0));
+	drop(extract);
+	assert_eq!(map.as_unpinned().keys().collect::<Vec<_>>(), vec![1, 3, 5]);
+	assert_eq!(map.len(), 3);
+}
+
+#[test]
+fn extract_keys_if_forgotten() {
+	use std::{mem, rc::Rc};
+
+	let drops = Rc::new(());
+	let map = PineMap::new();
+	for i in 0..10 {
+		map.insert(i, Rc::clone(&drops)).unwrap();
+	}
+	let mut map = map.pin();
+
+	let mut extract = PineMap::pinned_extract_keys_if(&mut map, |key| key % 3 == 0);
+	assert_eq!(extract.next(), Some(0));
+	assert_eq!(extract.next(), Some(3));
+	mem::forget(extract);
+
+	// Unvisited entries are still present, including those that would have matched.
+	assert_eq!(
+		map.as_unpinned().keys().collect::<Vec<_>>(),
+		vec![1, 2, 4, 5, 6, 7, 8, 9]
+	);
+	assert_eq!(Rc::strong_count(&drops), 9);
+	drop(map);
+	assert_eq!(Rc::strong_count(&drops), 1);
+}
+
+#[test]
+fn append_only() {
+	use pinus::sync::AppendOnly;
+
+	let strings = AppendOnly::new();
+	let words = "the quick brown fox jumps over the lazy dog the end".split(' ');
+	let interned = words
+		.map(|word| strings.get_or_insert_with(word.to_string(), |word| word.to_uppercase()))
+		.collect::<Vec<&String>>();
+
+	assert_eq!(strings.len(), 9);
+	assert!(std::ptr::eq(interned[0], interned[6]));
+	assert!(std::ptr::eq(interned[0], interned[9]));
+	assert_eq!(interned[3], "FOX");
+	assert_eq!(strings.get("lazy").map(String::as_str), Some("LAZY"));
+
+	let (first_key, first_value) = strings.iter().next().unwrap();
+	assert_eq!(
+		(first_key.as_str(), first_value.as_str()),
+		("brown", "BROWN")
+	);
+	assert_eq!(strings.iter().len(), 9);
+
+	let map = strings.into_inner();
+	assert_eq!(map.len(), 9);
+}
+
+#[test]
+fn replace_key() {
+	let mut map = PineMap::new();
+	map.insert("old".to_string(), 1).unwrap();
+	map.insert("taken".to_string(), 2).unwrap();
+	let address = map.stable_address("old");
+
+	assert_eq!(
+		map.replace_key("old", "new".to_string()),
+		Ok(Some("old".to_string()))
+	);
+	assert_eq!(map.get("old"), None);
+	assert_eq!(map.get("new"), Some(&1));
+	assert_eq!(map.stable_address("new"), address);
+
+	assert_eq!(
+		map.replace_key("new", "taken".to_string()),
+		Err("taken".to_string())
+	);
+	assert_eq!(map.get("new"), Some(&1));
+	assert_eq!(map.replace_key("missing", "other".to_string()), Ok(None));
+	assert_eq!(map.len(), 2);
+
+	let mut map = map.pin();
+	assert_eq!(
+		map.replace_key("new", "pinned".to_string()),
+		Ok(Some("new".to_string()))
+	);
+	assert_eq!(map.stable_address("pinned"), address);
+}
+
+#[test]
+fn clear_resets_after_key_panic() {
+	use std::panic::{catch_unwind, AssertUnwindSafe};
+
+	#[derive(PartialEq, Eq, PartialOrd, Ord)]
+	struct Key(u32);
+	impl Drop for Key {
+		fn drop(&mut self) {
+			if self.0 == 1 {
+				panic!("key drop")
+			}
+		}
+	}
+
+	let mut map = PressedPineMap::<Key, u64>::new();
+	for i in 0..3 {
+		map.emplace(Key(i), u64::from(i)).ok().unwrap();
+	}
+	assert!(catch_unwind(AssertUnwindSafe(|| map.clear())).is_err());
+	assert!(map.is_empty());
+	assert_eq!(map.allocated_bytes(), 0);
+
+	let mut map = PineMap::<Key, u64>::new();
+	for i in 0..3 {
+		map.insert(Key(i), u64::from(i)).ok().unwrap();
+	}
+	assert!(catch_unwind(AssertUnwindSafe(|| map.clear())).is_err());
+	assert!(map.is_empty());
+	assert_eq!(map.allocated_bytes(), 0);
+	map.insert(Key(2), 2).ok().unwrap();
+}
+
+#[test]
+fn pressed_clear() {
+	use std::{any::Any, cell::Cell, rc::Rc};
+
+	let mut map = PressedPineMap::<u32, u64>::new();
+	for i in 0..10_000 {
+		map.emplace(i, u64::from(i)).unwrap();
+	}
+	map.clear();
+	assert!(map.is_empty());
+	map.emplace(1, 1).unwrap();
+	assert_eq!(map.get(&1), Some(&1));
+
+	struct Observer(Rc<Cell<usize>>);
+	impl Drop for Observer {
+		fn drop(&mut self) {
+			self.0.set(self.0.get() + 1);
+		}
+	}
+	pinus::borrow_unsized!(Observer => dyn Any);
+
+	let drops = Rc::new(Cell::new(0));
+	let mut map = PressedPineMap::<u32, dyn Any>::new();
+	for i in 0..3 {
+		map.emplace(i, Observer(Rc::clone(&drops))).ok().unwrap();
+	}
+	map.clear();
+	assert_eq!(drops.get(), 3);
+
+	// Values placed outside their slot are dropped too.
+	map.emplace_with(0, |_, _: &mut std::mem::MaybeUninit<()>| {
+		Box::leak(Box::new(Observer(Rc::clone(&drops)))) as &mut dyn Any
+	})
+	.ok()
+	.unwrap();
+	drop(map);
+	assert_eq!(drops.get(), 4);
+}
+
+#[test]
+fn pin_mut_receivers() {
+	struct Outer {
+		map: PineMap<u8, String>,
+		_pinned: PhantomPinned,
+	}
+
+	impl Outer {
+		fn map(self: Pin<&mut Self>) -> Pin<&mut PineMap<u8, String>> {
+			unsafe { self.map_unchecked_mut(|outer| &mut outer.map) }
+		}
+	}
+
+	let mut outer = Box::pin(Outer {
+		map: PineMap::new(),
+		_pinned: PhantomPinned,
+	});
+
+	outer
+		.as_mut()
+		.map()
+		.insert_pin_mut(1, "one".to_string())
+		.unwrap();
+	outer
+		.as_mut()
+		.map()
+		.emplace_with_pin_mut(2, |_, slot| slot.write("two".to_string()))
+		.ok()
+		.unwrap();
+	outer
+		.as_mut()
+		.map()
+		.emplace_pin_mut(3, "three".to_string())
+		.unwrap();
+
+	outer.as_mut().map().get_pin_mut(&1).unwrap().push('!');
+	assert_eq!(outer.map.get(&1).map(String::as_str), Some("one!"));
+	assert_eq!(outer.map.len(), 3);
+}
+
+#[test]
+fn total_memory_estimate() {
+	let map = PineMap::new();
+	assert_eq!(map.total_memory_estimate(), 0);
+
+	map.insert(0_u64, 0_u64).unwrap();
+	let one = map.total_memory_estimate();
+	assert!(one > map.allocated_bytes());
+
+	for i in 1..1000 {
+		map.insert(i, i).unwrap();
+	}
+	let many = map.total_memory_estimate();
+	assert!(many > map.allocated_bytes());
+	assert!(many > one * 10);
+
+	let pressed = PressedPineMap::<_, u64>::new();
+	pressed.emplace(0_u64, 0).unwrap();
+	assert!(pressed.total_memory_estimate() > pressed.allocated_bytes());
+}
+
+#[test]
+fn build_or_get() {
+	let map = PineMap::new();
+	assert_eq!(
+		map.build_or_get(1, |key| key.to_string()),
+		Ok(&"1".to_string())
+	);
+	assert_eq!(
+		map.build_or_get(1, |_| "built".to_string()),
+		Err("built".to_string())
+	);
+	assert_eq!(map.get(&1).map(String::as_str), Some("1"));
+
+	let map = map.pin();
+	assert_eq!(
+		map.build_or_get(1, |_| "pinned".to_string()).err(),
+		Some("pinned".to_string())
+	);
+}
+
+#[test]
+fn remove_into_pinned_box_unchecked() {
+	struct SelfAware {
+		value: u8,
+		_pinned: PhantomPinned,
+	}
+
+	let mut map = PineMap::new().pin();
+	map.insert(
+		1,
+		SelfAware {
+			value: 7,
+			_pinned: PhantomPinned,
+		},
+	)
+	.1
+	.ok()
+	.unwrap();
+
+	// Nothing recorded the value's address, so moving it once is fine.
+	let boxed: Pin<Box<SelfAware>> = unsafe { map.remove_into_pinned_box_unchecked(&1) }.unwrap();
+	assert_eq!(boxed.as_ref().value, 7);
+	assert!(map.is_empty());
+	assert!(unsafe { map.remove_into_pinned_box_unchecked(&1) }.is_none());
+}
+
+#[test]
+fn clone_from_reuses_arena() {
+	let mut source = PineMap::new();
+	let mut destination = PineMap::new();
+	let mut working_set = None;
+
+	for round in 0..10_u64 {
+		source.clear();
+		for i in 0..100 {
+			source.insert(i, i * round).unwrap();
+		}
+
+		destination.clone_from(&source);
+		assert_eq!(destination, source);
+		assert_eq!(destination.hole_count(), 0);
+
+		let allocated = destination.allocated_bytes();
+		assert!(allocated <= *working_set.get_or_insert(allocated));
+	}
+
+	let clone = destination.clone();
+	assert_eq!(clone, destination);
+}
+
+#[test]
+fn range_as_map() {
+	let map = PineMap::new();
+	for i in 0..10 {
+		map.insert(i, i * 10).unwrap();
+	}
+
+	let submap = map.range_as_map(3..6);
+	assert_eq!(submap.len(), 3);
+	assert_eq!(
+		submap.into_iter().collect::<Vec<_>>(),
+		vec![(3, &30), (4, &40), (5, &50)]
+	);
+	assert!(map.range_as_map(10..).is_empty());
+
+	let map = map.pin();
+	let pinned = map.range_as_map(..=1);
+	assert_eq!(
+		pinned.values().map(|value| **value).collect::<Vec<_>>(),
+		vec![0, 10]
+	);
+}
+
+#[test]
+fn drop_phase() {
+	use pinus::sync::DropPhase;
+	use std::{cell::RefCell, cmp::Ordering};
+
+	struct Tracked<'a>(u8, &'static str, &'a RefCell<Vec<(&'static str, u8)>>);
+	impl Drop for Tracked<'_> {
+		fn drop(&mut self) {
+			self.2.borrow_mut().push((self.1, self.0));
+		}
+	}
+	impl PartialEq for Tracked<'_> {
+		fn eq(&self, other: &Self) -> bool {
+			self.0 == other.0
+		}
+	}
+	impl Eq for Tracked<'_> {}
+	impl PartialOrd for Tracked<'_> {
+		fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+			Some(self.cmp(other))
+		}
+	}
+	impl Ord for Tracked<'_> {
+		fn cmp(&self, other: &Self) -> Ordering {
+			self.0.cmp(&other.0)
+		}
+	}
+
+	fn fill<'a>(
+		map: &PineMap<Tracked<'a>, Tracked<'a>>,
+		log: &'a RefCell<Vec<(&'static str, u8)>>,
+	) {
+		for i in 0..3 {
+			map.insert(Tracked(i, "key", log), Tracked(i, "value", log))
+				.ok()
+				.unwrap();
+		}
+	}
+
+	let log = RefCell::new(vec![]);
+
+	let map = PineMap::with_drop_phase(DropPhase::KeysThenValues);
+	fill(&map, &log);
+	drop(map);
+	assert_eq!(
+		log.replace(vec![]),
+		vec![
+			("key", 0),
+			("key", 1),
+			("key", 2),
+			("value", 0),
+			("value", 1),
+			("value", 2)
+		]
+	);
+
+	let mut map = PineMap::with_drop_phase(DropPhase::ValuesThenKeys);
+	fill(&map, &log);
+	map.clear();
+	assert_eq!(
+		log.replace(vec![]),
+		vec![
+			("value", 0),
+			("value", 1),
+			("value", 2),
+			("key", 0),
+			("key", 1),
+			("key", 2)
+		]
+	);
+	drop(map);
+
+	let map = PineMap::new();
+	fill(&map, &log);
+	drop(map);
+	assert_eq!(
+		log.replace(vec![]),
+		vec![
+			("key", 0),
+			("value", 0),
+			("key", 1),
+			("value", 1),
+			("key", 2),
+			("value", 2)
+		]
+	);
+}
+
+#[test]
+fn modify() {
+	let mut map = PineMap::new();
+	map.insert(1, 10).unwrap();
+	map.insert(2, 20).unwrap();
+
+	assert_eq!(
+		map.modify(&1, |value| std::mem::replace(value, 11)),
+		Some(10)
+	);
+	assert_eq!(
+		map.modify(&2, |value| {
+			*value += 1;
+			*value
+		}),
+		Some(21)
+	);
+	assert_eq!(map.modify(&3, |value| *value), None);
+	assert_eq!(map.get(&1), Some(&11));
+
+	let mut map = map.pin();
+	assert_eq!(
+		map.modify_pin(&1, |mut value| {
+			*value += 1;
+			*value
+		}),
+		Some(12)
+	);
+	assert_eq!(map.modify_pin(&2, |value| *value), Some(21));
+}
+
+#[cfg(feature = "debug-audit")]
+#[test]
+fn audit_after_churn() {
+	let mut map = PineMap::new();
+	map.audit();
+	for round in 0..5_u64 {
+		for i in 0..20 {
+			map.insert(i, i * round).ok();
+		}
+		map.retain(|key, _| key % 3 != round % 3);
+		map.audit();
+		let _ = map.drain_range(5..10).count();
+		map.audit();
+	}
+	map.clear();
+	map.audit();
+}
+
+#[cfg(feature = "debug-audit")]
+#[test]
+#[should_panic = "two live values share the address"]
+fn audit_detects_aliasing() {
+	let shared: *mut u64 = Box::leak(Box::new(0));
+	let map = PineMap::new();
+	for key in 0..2 {
+		// Deliberately corrupts the instance by placing two values at the same address.
+		map.emplace_with(key, |_, _| unsafe { &mut *shared })
+			.ok()
+			.unwrap();
+	}
+	map.audit();
+}
+
+#[test]
+fn extend_reserves() {
+	let mut map = PineMap::new();
+	map.extend((0..1000_u64).map(|i| (i, i)));
+	assert_eq!(map.len(), 1000);
+	// A single contiguous reservation.
+	assert_eq!(map.try_values_as_slice().map(<[_]>::len), Some(1000));
+	assert_eq!(map.hole_count(), 0);
+
+	// A lower bound of zero still works, just with incremental growth.
+	let mut map = PineMap::new();
+	map.extend((0..1000_u64).filter(|_| true).map(|i| (i, i * 2)));
+	assert_eq!(map.len(), 1000);
+	assert_eq!(map.get(&999), Some(&1998));
+
+	// Existing keys are kept.
+	map.extend(vec![(0, 1), (1000, 2000)]);
+	assert_eq!(map.get(&0), Some(&0));
+	assert_eq!(map.get(&1000), Some(&2000));
+	assert_eq!(map.hole_count(), 1);
+}
+
+#[test]
+fn first_and_last_value() {
+	let map = PineMap::new();
+	assert_eq!(map.first_value(), None);
+	assert_eq!(map.last_value(), None);
+
+	for key in [5, 2, 8, 1, 9, 3] {
+		map.insert(key, key * 10).unwrap();
+	}
+	assert_eq!(map.first_value(), Some(&10));
+	assert_eq!(map.last_value(), Some(&90));
+
+	let map = map.pin();
+	assert_eq!(map.first_value().map(|value| *value), Some(10));
+	assert_eq!(map.last_value().map(|value| *value), Some(90));
+}
+
+#[test]
+fn retain_and_compact() {
+	let mut map = PineMap::new();
+	for i in 0..1000_u64 {
+		map.insert(i, i).unwrap();
+	}
+	let before = map.allocated_bytes();
+
+	assert_eq!(map.retain_and_compact(|key, _| *key == 500), 999);
+	assert_eq!(map.len(), 1);
+	assert_eq!(map.get(&500), Some(&500));
+	assert_eq!(map.hole_count(), 0);
+	assert!(map.allocated_bytes() < before);
+
+	// Few removals leave the arena as is.
+	for i in 0..10_u64 {
+		map.insert(i, i).unwrap();
+	}
+	assert_eq!(map.retain_and_compact(|key, _| *key != 0), 1);
+	assert_eq!(map.hole_count(), 1);
+}
+
+#[test]
+fn filter_map_values() {
+	use std::{any::Any, fmt::Display};
+
+	trait Shown: Display + Any {
+		fn as_any(&self) -> &dyn Any;
+	}
+	impl<T: Display + Any> Shown for T {
+		fn as_any(&self) -> &dyn Any {
+			self
+		}
+	}
+
+	let map = PressedPineMap::<_, dyn Shown>::new();
+	map.emplace_boxed(1, Box::new(10_u32)).ok().unwrap();
+	map.emplace_boxed(2, Box::new("two")).ok().unwrap();
+	map.emplace_boxed(3, Box::new(30_u32)).ok().unwrap();
+	map.emplace_boxed(4, Box::new('c')).ok().unwrap();
+
+	let numbers = map
+		.filter_map_values(|value| value.as_any().downcast_ref::<u32>())
+		.collect::<Vec<_>>();
+	assert_eq!(numbers, vec![(1, &10), (3, &30)]);
+
+	let shown = map
+		.filter_map_values(|value| Some(value))
+		.map(|(key, value)| format!("{}={}", key, value))
+		.collect::<Vec<_>>();
+	assert_eq!(shown, vec!["1=10", "2=two", "3=30", "4=c"]);
+}
+
+#[test]
+fn insert_or_inspect() {
+	let map = PineMap::new();
+	let mut seen = None;
+	let (value, result) = map.insert_or_inspect(1, "one", |existing| seen = Some(*existing));
+	assert_eq!((*value, result), ("one", Ok(())));
+	assert_eq!(seen, None);
+
+	let (value, result) = map.insert_or_inspect(1, "uno", |existing| seen = Some(*existing));
+	assert_eq!((*value, result), ("one", Err((1, "uno"))));
+	assert_eq!(seen, Some("one"));
+
+	let map = map.pin();
+	let mut seen = None;
+	map.insert_or_inspect(1, "eins", |existing| seen = Some(*existing))
+		.1
+		.unwrap_err();
+	assert_eq!(seen, Some("one"));
+
+	let map = PressedPineMap::new();
+	let mut seen = None;
+	assert_eq!(map.insert_or_inspect(1, 10, |_| unreachable!()).1, Ok(()));
+	let (value, result) = map.insert_or_inspect(1, 11, |existing| seen = Some(*existing));
+	assert_eq!((*value, result), (10, Err((1, 11))));
+	assert_eq!(seen, Some(10));
+}
+
+#[test]
+fn try_range_as_mut_slice() {
+	let mut map = PineMap::new();
+	map.extend((0..100_u64).map(|i| (i, i)));
+
+	let window = map.try_range_as_mut_slice(10..20).unwrap();
+	assert_eq!(window.len(), 10);
+	for value in window {
+		*value *= 2;
+	}
+	assert_eq!(map.get(&9), Some(&9));
+	assert_eq!(map.get(&10), Some(&20));
+	assert_eq!(map.get(&19), Some(&38));
+	assert_eq!(map.get(&20), Some(&20));
+	assert_eq!(
+		map.try_range_as_mut_slice(200..).map(|window| window.len()),
+		Some(0)
+	);
+
+	// Churn leaves gaps and reuses holes out of key order.
+	map.remove_key(&15);
+	assert!(map.try_range_as_mut_slice(10..20).is_none());
+	assert!(map.try_range_as_mut_slice(..15).is_some());
+	map.insert(1000, 1000).unwrap();
+	assert!(map.try_range_as_mut_slice(90..).is_none());
+	assert!(map.try_range_as_mut_slice(90..100).is_some());
+}
+
+#[test]
+fn growth_observer() {
+	use std::sync::{Arc, Mutex};
+
+	let growth = Arc::new(Mutex::new(vec![]));
+	let mut map = PineMap::with_capacity(4);
+	let initial = map.allocated_bytes();
+	map.set_growth_observer(Box::new({
+		let growth = Arc::clone(&growth);
+		move |allocated_bytes| growth.lock().unwrap().push(allocated_bytes)
+	}));
+
+	for i in 0..4_u64 {
+		map.insert(i, i).unwrap();
+	}
+	assert!(growth.lock().unwrap().is_empty());
+
+	let mut i = 4;
+	while growth.lock().unwrap().is_empty() {
+		assert!(i < 10_000, "The observer wasn't called.");
+		map.insert(i, i).unwrap();
+		i += 1;
+	}
+	let growth = growth.lock().unwrap().clone();
+	assert_eq!(growth, vec![map.allocated_bytes()]);
+	assert!(growth[0] > initial);
+}
+
+#[test]
+fn remove_value_into() {
+	let mut map = PineMap::new();
+	map.insert(1, vec![1_u8, 2, 3]).unwrap();
+
+	let mut out = None;
+	assert!(!map.remove_value_into(&2, &mut out));
+	assert_eq!(out, None);
+	assert!(map.remove_value_into(&1, &mut out));
+	assert_eq!(out, Some(vec![1, 2, 3]));
+	assert!(map.is_empty());
+	assert_eq!(map.hole_count(), 1);
+}
+
+#[test]
+fn ref_snapshot_unchecked() {
+	let map = PineMap::new();
+	for i in [3, 1, 2] {
+		map.insert(i.to_string(), i * 10).unwrap();
+	}
+
+	// Nothing is inserted while the snapshot is in use.
+	let snapshot = unsafe { map.ref_snapshot_unchecked() };
+	assert_eq!(map.len(), 3); // Not locked anymore.
+	assert_eq!(
+		snapshot
+			.iter()
+			.map(|(key, value)| (key.as_str(), **value))
+			.collect::<Vec<_>>(),
+		vec![("1", 10), ("2", 20), ("3", 30)]
+	);
+
+	// Value references alone remain valid across insertions.
+	let values = snapshot
+		.into_iter()
+		.map(|(_, value)| value)
+		.collect::<Vec<_>>();
+	map.insert("4".to_string(), 40).unwrap();
+	assert_eq!(values, vec![&10, &20, &30]);
+}
+
+#[test]
+fn pressed_into_iter() {
+	use std::any::Any;
+
+	let map = PressedPineMap::<u32, dyn Any>::new();
+	map.emplace_boxed(1, Box::new(1_u8)).ok().unwrap();
+	map.emplace_boxed(2, Box::new("two".to_string()))
+		.ok()
+		.unwrap();
+	map.emplace_boxed(3, Box::new([3_u64; 4])).ok().unwrap();
+	map.emplace_boxed(4, Box::new(())).ok().unwrap();
+
+	let mut entries = map.into_iter();
+	assert_eq!(entries.len(), 4);
+	let (key, value) = entries.next().unwrap();
+	assert_eq!((key, value.downcast::<u8>().ok()), (1, Some(Box::new(1))));
+	let (key, value) = entries.next().unwrap();
+	assert_eq!(
+		(key, value.downcast::<String>().ok()),
+		(2, Some(Box::new("two".to_string())))
+	);
+	let (key, value) = entries.next_back().unwrap();
+	assert_eq!((key, value.downcast::<()>().ok()), (4, Some(Box::new(()))));
+	let (key, value) = entries.next().unwrap();
+	assert_eq!(
+		(key, value.downcast::<[u64; 4]>().ok()),
+		(3, Some(Box::new([3; 4])))
+	);
+	assert!(entries.next().is_none());
+
+	// Partial consumption drops the rest.
+	let dropped = std::rc::Rc::new(());
+	let map = PressedPineMap::<u32, dyn Any>::new();
+	for i in 0..3 {
+		map.emplace_boxed(i, Box::new(std::rc::Rc::clone(&dropped)))
+			.ok()
+			.unwrap();
+	}
+	let mut entries = map.into_iter();
+	let _first = entries.next().unwrap();
+	drop(entries);
+	assert_eq!(std::rc::Rc::strong_count(&dropped), 2);
+}
+
+#[test]
+fn pinned_handle() {
+	let mut map = PressedPineMap::<_, str>::new();
+	map.emplace_boxed(1, "one".into()).ok().unwrap();
+	let handle = map.get_handle(&1).unwrap();
+	assert!(map.get_handle(&2).is_none());
+
+	for i in 2..100 {
+		map.emplace_boxed(i, i.to_string().into()).ok().unwrap();
+	}
+	assert_eq!(handle.get(&map), Some("one"));
+
+	// Handles don't resolve through other instances.
+	let other = PressedPineMap::<_, str>::new();
+	other.emplace_boxed(1, "one".into()).ok().unwrap();
+	assert_eq!(handle.get(&other), None);
+
+	let removed = map.get_handle(&2).unwrap();
+	map.remove_boxed(&2).unwrap();
+	assert_eq!(removed.get(&map), None);
+	assert_eq!(handle.get(&map), None);
+
+	let handle = map.get_handle(&1).unwrap();
+	assert_eq!(handle.get(&map), Some("one"));
+	map.clear();
+	assert_eq!(handle.get(&map), None);
+
+	map.emplace_boxed(1, "uno".into()).ok().unwrap();
+	let map = map.pin();
+	let handle = map.as_unpinned().get_handle(&1).unwrap();
+	assert_eq!(handle.get_pinned(&map).as_deref(), Some("uno"));
+}
+
+#[test]
+fn entry_or_insert_with_key() {
+	let map = PineMap::new();
+	assert_eq!(map.entry(3).or_insert_with_key(|key| key * 10), &30);
+	assert_eq!(
+		map.entry(3)
+			.or_insert_with_key(|_| unreachable!("The entry is occupied.")),
+		&30
+	);
+	assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn swap_remove() {
+	let mut map = PressedPineMap::<_, u64>::new();
+	for i in 0..5 {
+		map.emplace(i, i * 10).unwrap();
+	}
+	let allocated = map.allocated_bytes();
+	let addresses = map.value_ptrs();
+
+	assert_eq!(map.swap_remove(&2), Some(20));
+	assert_eq!(map.swap_remove(&2), None);
+	assert!(map.allocated_bytes() < allocated);
+	// The most recent value moved into the freed slot.
+	assert_eq!(map.get(&4), Some(&40));
+	assert_eq!(map.value_ptrs()[3], addresses[2]);
+
+	// The next allocation reuses the rewound space.
+	map.emplace(5, 50).unwrap();
+	assert_eq!(map.allocated_bytes(), allocated);
+	assert_eq!(map.value_ptrs()[4], addresses[4]);
+	assert_eq!(
+		(0..6).map(|key| map.get(&key).copied()).collect::<Vec<_>>(),
+		vec![Some(0), Some(10), None, Some(30), Some(40), Some(50)]
+	);
+
+	// Removing the most recent value only rewinds.
+	assert_eq!(map.swap_remove(&5), Some(50));
+	assert!(map.allocated_bytes() < allocated);
+}
+
+#[test]
+fn panicking_factory() {
+	use std::panic::{catch_unwind, AssertUnwindSafe};
+
+	let map = PineMap::new();
+	map.insert(1, 1).unwrap();
+
+	for _ in 0..3 {
+		assert!(catch_unwind(AssertUnwindSafe(|| {
+			map.get_or_insert_with_status(2, |_| panic!("factory"));
+		}))
+		.is_err());
+		assert!(catch_unwind(AssertUnwindSafe(|| {
+			let _ = map.try_insert_with(3, |_| -> Result<_, Infallible> { panic!("factory") });
+		}))
+		.is_err());
+	}
+
+	// No phantom keys, and the allocated slot was kept for reuse.
+	assert_eq!(map.len(), 1);
+	assert!(!map.contains_key(&2));
+	assert!(!map.contains_key(&3));
+	assert_eq!(map.hole_count(), 1);
+
+	let allocated = map.allocated_bytes();
+	assert_eq!(
+		map.get_or_insert_with_status(2, |key| key * 10),
+		(&20, true)
+	);
+	map.insert(3, 30).unwrap();
+	assert_eq!(map.get(&2), Some(&20));
+	assert_eq!(map.get(&3), Some(&30));
+	assert_eq!(map.len(), 3);
+	assert_eq!(map.hole_count(), 0);
+	assert!(map.allocated_bytes() > allocated);
+}
+
+#[test]
+fn range_bounds() {
+	use std::{
+		collections::BTreeMap,
+		ops::Bound,
+		panic::{catch_unwind, AssertUnwindSafe},
+	};
+
+	let mut map = PineMap::new();
+	let mut reference = BTreeMap::new();
+	for i in 0..10 {
+		map.insert(i, i * 10).unwrap();
+		reference.insert(i, i * 10);
+	}
+
+	let bounds = [
+		Bound::Unbounded,
+		Bound::Included(&3),
+		Bound::Excluded(&3),
+		Bound::Included(&6),
+		Bound::Excluded(&6),
+		Bound::Included(&20),
+		Bound::Excluded(&20),
+	];
+	for &start in &bounds {
+		for &end in &bounds {
+			let expected = catch_unwind(|| {
+				reference
+					.range((start, end))
+					.map(|(&key, &value)| (key, value))
+					.collect::<Vec<_>>()
+			});
+			let by_bounds = catch_unwind(AssertUnwindSafe(|| {
+				map.range_bounds(start, end)
+					.map(|(key, &value)| (key, value))
+					.collect::<Vec<_>>()
+			}));
+			let by_range = catch_unwind(AssertUnwindSafe(|| {
+				map.range((start, end))
+					.map(|(key, &value)| (key, value))
+					.collect::<Vec<_>>()
+			}));
+			let by_bounds_mut = catch_unwind(AssertUnwindSafe(|| {
+				map.range_bounds_mut(start, end)
+					.map(|(&key, &mut value)| (key, value))
+					.collect::<Vec<_>>()
+			}));
+			match expected {
+				Ok(expected) => {
+					assert_eq!(by_bounds.ok(), Some(expected.clone()));
+					assert_eq!(by_range.ok(), Some(expected.clone()));
+					assert_eq!(by_bounds_mut.ok(), Some(expected));
+				}
+				Err(_) => {
+					assert!(by_bounds.is_err());
+					assert!(by_range.is_err());
+					assert!(by_bounds_mut.is_err());
+				}
+			}
+		}
+	}
+
+	// The read lock is released after a panic.
+	map.insert(10, 100).ok().unwrap();
+
+	let mut map = map.pin();
+	assert_eq!(
+		map.range_bounds(Bound::Excluded(&3), Bound::Included(&5))
+			.map(|(key, value)| (key, *value))
+			.collect::<Vec<_>>(),
+		[(4, 40), (5, 50)]
+	);
+	assert_eq!(
+		map.range(8..)
+			.rev()
+			.map(|(key, value)| (key, *value))
+			.collect::<Vec<_>>(),
+		[(10, 100), (9, 90), (8, 80)]
+	);
+	for (_, mut value) in map.range_bounds_pinned_mut(Bound::Excluded(&7), Bound::Unbounded) {
+		*value += 1;
+	}
+	assert_eq!(map.get(&7).map(|value| *value), Some(70));
+	assert_eq!(map.get(&8).map(|value| *value), Some(81));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_clear() {
+	use pinus::sync::DropPhase;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	struct Counted<'a>(&'a AtomicUsize, Vec<u64>);
+	impl Drop for Counted<'_> {
+		fn drop(&mut self) {
+			self.0
+				.fetch_add(self.1.iter().sum::<u64>() as usize, Ordering::Relaxed);
+		}
+	}
+
+	fn fill<'a>(map: &PineMap<u64, Counted<'a>>, dropped: &'a AtomicUsize) {
+		for i in 0..100_000 {
+			map.insert(i, Counted(dropped, vec![i % 7; 3]))
+				.ok()
+				.unwrap();
+		}
+	}
+
+	let serial = AtomicUsize::new(0);
+	let mut map = PineMap::new();
+	fill(&map, &serial);
+	map.clear();
+	assert!(map.is_empty());
+
+	let parallel = AtomicUsize::new(0);
+	for drop_phase in [
+		DropPhase::Interleaved,
+		DropPhase::ValuesThenKeys,
+		DropPhase::KeysThenValues,
+	] {
+		parallel.store(0, Ordering::Relaxed);
+		let mut map = PineMap::with_drop_phase(drop_phase);
+		fill(&map, &parallel);
+		map.par_clear();
+		assert!(map.is_empty());
+		assert_eq!(
+			parallel.load(Ordering::Relaxed),
+			serial.load(Ordering::Relaxed)
+		);
+
+		// The instance stays usable.
+		fill(&map, &parallel);
+		assert_eq!(map.len(), 100_000);
+	}
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_clear_panics() {
+	struct Bomb(bool);
+	impl Drop for Bomb {
+		fn drop(&mut self) {
+			if self.0 {
+				panic!("boom")
+			}
+		}
+	}
+
+	let mut map = PineMap::new();
+	for i in 0..10_000 {
+		map.insert(i, Bomb(i % 1000 == 0)).ok().unwrap();
+	}
+	let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| map.par_clear()))
+		.expect_err("expected a panic");
+	assert_eq!(
+		panic
+			.downcast_ref::<Vec<Box<dyn std::any::Any + Send>>>()
+			.map(Vec::len),
+		Some(10)
+	);
+
+	// Not poisoned.
+	assert!(map.is_empty());
+	map.insert(0, Bomb(false)).ok().unwrap();
+}
+
+#[test]
+fn get_key() {
+	use std::{borrow::Borrow, cmp::Ordering};
+
+	#[derive(Debug, Clone)]
+	struct Tagged(u8, &'static str);
+	impl PartialEq for Tagged {
+		fn eq(&self, other: &Self) -> bool {
+			self.0 == other.0
+		}
+	}
+	impl Eq for Tagged {}
+	impl PartialOrd for Tagged {
+		fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+			Some(self.cmp(other))
+		}
+	}
+	impl Ord for Tagged {
+		fn cmp(&self, other: &Self) -> Ordering {
+			self.0.cmp(&other.0)
+		}
+	}
+	impl Borrow<u8> for Tagged {
+		fn borrow(&self) -> &u8 {
+			&self.0
+		}
+	}
+
+	let map = PineMap::new();
+	map.insert(Tagged(1, "stored"), ()).ok().unwrap();
+	assert_eq!(map.get_key(&1).map(|key| key.1), Some("stored"));
+	assert_eq!(
+		map.get_key(&Tagged(1, "lookup")).map(|key| key.1),
+		Some("stored")
+	);
+	assert_eq!(map.get_key(&2), None);
+
+	let map = Box::pin(map);
+	assert_eq!(map.get_key(&1).map(|key| key.1), Some("stored"));
+}
+
+#[test]
+fn insert_all_or_rollback() {
+	let mut map = PineMap::new();
+	map.insert(2, "two").ok().unwrap();
+	let holes = map.hole_count();
+	let bytes = map.allocated_bytes();
+
+	let batch = vec![(1, "one"), (2, "deux"), (3, "three")];
+	assert_eq!(map.insert_all_or_rollback(batch.clone()), Err(batch));
+	assert_eq!(map.len(), 1);
+	assert_eq!(map.get(&2), Some(&"two"));
+	assert_eq!(map.hole_count(), holes);
+	assert_eq!(map.allocated_bytes(), bytes);
+
+	// Duplicates within the batch also conflict.
+	let batch = vec![(1, "one"), (3, "three"), (1, "uno")];
+	assert_eq!(map.insert_all_or_rollback(batch.clone()), Err(batch));
+	assert_eq!(map.len(), 1);
+
+	assert_eq!(
+		map.insert_all_or_rollback(vec![(1, "one"), (3, "three")]),
+		Ok(())
+	);
+	assert_eq!(map.values_vec(), [&"one", &"two", &"three"]);
+}
+
+#[test]
+fn to_vec() {
+	let map = PineMap::new();
+	for key in [3_u32, 1, 4, 5, 9, 2, 6] {
+		map.insert(key, u64::from(key) * 10).ok().unwrap();
+	}
+	assert_eq!(
+		map.to_vec(),
+		vec![
+			(1, 10),
+			(2, 20),
+			(3, 30),
+			(4, 40),
+			(5, 50),
+			(6, 60),
+			(9, 90)
+		]
+	);
+
+	let map = Box::pin(map);
+	assert_eq!(map.to_vec().len(), 7);
+	assert_eq!(PineMap::<u8, u8>::new().to_vec(), vec![]);
+}
+
+#[test]
+fn modify_pin_reborrow() {
+	struct Counter(u32, PhantomPinned);
+	impl Counter {
+		fn bump(self: Pin<&mut Self>) {
+			unsafe { self.get_unchecked_mut() }.0 += 1
+		}
+		fn double(self: Pin<&mut Self>) -> u32 {
+			let this = unsafe { self.get_unchecked_mut() };
+			this.0 *= 2;
+			this.0
+		}
+	}
+
+	let map = PineMap::new();
+	map.insert(1, Counter(1, PhantomPinned)).ok().unwrap();
+	let mut map = map.pin();
+	assert_eq!(
+		map.modify_pin(&1, |mut counter| {
+			counter.as_mut().bump();
+			counter.as_mut().bump();
+			counter.double()
+		}),
+		Some(6)
+	);
+
+	let mut counter = map.get_mut(&1).unwrap();
+	counter.as_mut().bump();
+	assert_eq!(counter.double(), 14);
+}
+
+#[test]
+fn entry_shared_and_mut() {
+	let map = PineMap::new();
+	let shared = &map;
+	let a = shared.entry(1).or_insert(10);
+	let b = shared.entry(2).or_insert_with(|| 20);
+	assert_eq!(shared.entry(1).or_insert(0), &10);
+	assert_eq!((a, b), (&10, &20));
+
+	let mut map = map;
+	*map.entry_mut(1)
+		.and_modify(|value| *value += 1)
+		.or_insert(0) += 100;
+	assert_eq!(
+		map.entry_mut(3)
+			.and_modify(|_| unreachable!())
+			.or_insert(30),
+		&mut 30
+	);
+	assert_eq!(map.to_vec(), vec![(1, 111), (2, 20), (3, 30)]);
+}
+
+#[test]
+fn pressed_with_capacity_is_in_bytes() {
+	fn is_contiguous(map: &PressedPineMap<u32, u8>) -> bool {
+		let addresses = map
+			.value_ptrs()
+			.into_iter()
+			.map(|value| value.as_ptr() as usize)
+			.collect::<Vec<_>>();
+		addresses.iter().max().unwrap() - addresses.iter().min().unwrap() == addresses.len() - 1
+	}
+
+	let map = PressedPineMap::with_capacity(4096);
+	for i in 0..4096 {
+		map.emplace(i, 0_u8).ok().unwrap();
+	}
+	assert!(is_contiguous(&map));
+
+	// Not scaled by the maximum alignment.
+	for i in 4096..4096 * 16 {
+		map.emplace(i, 0_u8).ok().unwrap();
+	}
+	assert!(!is_contiguous(&map));
+}
+
+// Also run under Miri in CI, as this would be unsound if inserting through a shared reference
+// created a `&mut` to an existing value that other threads are reading.
+#[test]
+fn get_default_concurrently() {
+	use std::{
+		sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc, Barrier,
+		},
+		thread,
+	};
+
+	static CREATED: AtomicUsize = AtomicUsize::new(0);
+	struct Tally(AtomicUsize);
+	impl Default for Tally {
+		fn default() -> Self {
+			CREATED.fetch_add(1, Ordering::SeqCst);
+			Self(AtomicUsize::new(0))
+		}
+	}
+
+	let map = Arc::new(PineMap::<&str, Tally>::new());
+	let barrier = Arc::new(Barrier::new(8));
+	let threads = (0..8)
+		.map(|_| {
+			let map = Arc::clone(&map);
+			let barrier = Arc::clone(&barrier);
+			thread::spawn(move || {
+				barrier.wait();
+				for _ in 0..100 {
+					map.get_default("shared").0.fetch_add(1, Ordering::Relaxed);
+				}
+			})
+		})
+		.collect::<Vec<_>>();
+	for thread in threads {
+		thread.join().unwrap();
+	}
+
+	assert_eq!(CREATED.load(Ordering::SeqCst), 1);
+	assert_eq!(map.len(), 1);
+	assert_eq!(map.get("shared").unwrap().0.load(Ordering::Relaxed), 800);
+}